@@ -0,0 +1,259 @@
+//! Parsing of the `#[scroll(...)]` helper attributes.
+
+use quote::quote;
+
+/// Attributes placed on the type being derived.
+#[derive(Default)]
+pub struct ContainerAttrs {
+    /// The integer type the discriminant tag of an enum is encoded as, `#[scroll(tag = u16)]`.
+    pub tag: Option<syn::Type>,
+    /// The context type the impls are generic over instead of `Endian`, `#[scroll(ctx = "MyCtx")]`.
+    pub ctx: Option<syn::Type>,
+    /// The alignment the size of a struct is rounded up to, `#[scroll(align = 8)]`.
+    pub align: Option<usize>,
+    /// The number of reserved bytes at the end of a struct, `#[scroll(pad = 3)]`.
+    pub pad: Option<usize>,
+    /// Lay out a struct like C does, aligning every field and the size of the struct to the
+    /// alignment of the types, `#[scroll(repr_c)]`.
+    pub repr_c: bool,
+    /// The bytes a struct starts with, checked before reading its fields and written in front of
+    /// them, `#[scroll(magic = b"\x7fELF")]`.
+    pub magic: Option<syn::LitByteStr>,
+}
+
+/// Attributes placed on an enum variant.
+#[derive(Default)]
+pub struct VariantAttrs {
+    /// The tag value identifying this variant, `#[scroll(tag = 0x10)]`.
+    pub tag: Option<syn::Expr>,
+}
+
+/// Attributes placed on a field of a struct or enum variant.
+#[derive(Default)]
+pub struct FieldAttrs {
+    /// A fixed byte order overriding the outer context, `#[scroll(endian = "be")]`.
+    pub endian: Option<proc_macro2::TokenStream>,
+    /// The name of a previously read field holding the number of elements of this `Vec`,
+    /// `#[scroll(count = "len")]`.
+    pub count: Option<String>,
+    /// The integer type of the element count written in front of this `Vec`,
+    /// `#[scroll(len_prefix = u16)]`.
+    pub len_prefix: Option<syn::Type>,
+    /// An expression computing the context of this field from the outer `ctx` and the fields
+    /// preceding it, `#[scroll(ctx = StrCtx::Length(self.len as usize))]`.
+    pub ctx: Option<syn::Expr>,
+    /// The number of bits of this field, packed most significant bit first together with the
+    /// neighbouring bit fields, `#[scroll(bits = 4)]`.
+    pub bits: Option<u32>,
+    /// The alignment of the offset of this field from the start of the type,
+    /// `#[scroll(align = 8)]`.
+    pub align: Option<usize>,
+    /// The number of reserved bytes in front of this field, `#[scroll(pad = 3)]`.
+    pub pad: Option<usize>,
+    /// A condition the field read has to meet, which can refer to it and the fields preceding it,
+    /// `#[scroll(assert = "self.version <= 2")]`.
+    pub assert: Option<syn::Expr>,
+    /// The value the field read has to have, which is also written in place of the field,
+    /// `#[scroll(const = 0)]`.
+    pub constant: Option<syn::Expr>,
+}
+
+/// A field's `ctx` replaces the outer context, so the byte order has to be part of it instead.
+const ENDIAN_AND_CTX: &str =
+    "a field can not have both an endian and a ctx, give the byte order in the ctx instead";
+
+/// Parses either `T` or `"T"`, the latter for symmetry with the other string valued attributes.
+fn parse_quoted_or<T: syn::parse::Parse>(input: syn::parse::ParseStream) -> syn::Result<T> {
+    if input.peek(syn::LitStr) {
+        input.parse::<syn::LitStr>()?.parse()
+    } else {
+        input.parse()
+    }
+}
+
+/// Parses the value of `align = N`, which must be a power of two.
+fn parse_align(meta: &syn::meta::ParseNestedMeta) -> syn::Result<usize> {
+    let align: syn::LitInt = meta.value()?.parse()?;
+    match align.base10_parse::<usize>()? {
+        align if align.is_power_of_two() => Ok(align),
+        _ => Err(meta.error("align must be a power of two")),
+    }
+}
+
+/// Parses the value of `pad = N`.
+fn parse_pad(meta: &syn::meta::ParseNestedMeta) -> syn::Result<usize> {
+    let pad: syn::LitInt = meta.value()?.parse()?;
+    pad.base10_parse()
+}
+
+fn parse_scroll_attrs<F>(attrs: &[syn::Attribute], mut f: F)
+where
+    F: FnMut(syn::meta::ParseNestedMeta) -> syn::Result<()>,
+{
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("scroll")) {
+        if let Err(err) = attr.parse_nested_meta(&mut f) {
+            panic!("invalid #[scroll] attribute: {}", err);
+        }
+    }
+}
+
+impl ContainerAttrs {
+    pub fn parse(attrs: &[syn::Attribute]) -> Self {
+        let mut res = Self::default();
+        parse_scroll_attrs(attrs, |meta| {
+            if meta.path.is_ident("tag") {
+                res.tag = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("ctx") {
+                res.ctx = Some(parse_quoted_or(meta.value()?)?);
+                Ok(())
+            } else if meta.path.is_ident("align") {
+                res.align = Some(parse_align(&meta)?);
+                Ok(())
+            } else if meta.path.is_ident("pad") {
+                res.pad = Some(parse_pad(&meta)?);
+                Ok(())
+            } else if meta.path.is_ident("repr_c") {
+                res.repr_c = true;
+                Ok(())
+            } else if meta.path.is_ident("magic") {
+                res.magic = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported scroll attribute"))
+            }
+        });
+        res
+    }
+
+    /// Whether the type has any of the layout attributes, which only structs can have.
+    pub fn has_layout(&self) -> bool {
+        self.align.is_some() || self.pad.is_some() || self.repr_c || self.magic.is_some()
+    }
+
+    /// The number of bytes of the magic the type starts with.
+    pub fn magic_len(&self) -> usize {
+        self.magic.as_ref().map_or(0, |magic| magic.value().len())
+    }
+
+    /// The context type of the generated impls.
+    pub fn ctx_type(&self) -> proc_macro2::TokenStream {
+        match self.ctx {
+            Some(ref ty) => quote! { #ty },
+            None => quote! { ::scroll::Endian },
+        }
+    }
+
+    /// The context the tag of an enum is read and written with, given the name of the outer
+    /// context: the outer context itself, or the `Endian` converted from a custom context with
+    /// `From`, which the context type has to implement.
+    pub fn tag_ctx(&self, ctx: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        match self.ctx {
+            Some(_) => quote! { ::scroll::Endian::from(#ctx) },
+            None => ctx,
+        }
+    }
+}
+
+impl VariantAttrs {
+    pub fn parse(attrs: &[syn::Attribute]) -> Self {
+        let mut res = Self::default();
+        parse_scroll_attrs(attrs, |meta| {
+            if meta.path.is_ident("tag") {
+                res.tag = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported scroll attribute"))
+            }
+        });
+        res
+    }
+}
+
+impl FieldAttrs {
+    pub fn parse(attrs: &[syn::Attribute]) -> Self {
+        let mut res = Self::default();
+        parse_scroll_attrs(attrs, |meta| {
+            if meta.path.is_ident("endian") {
+                if res.ctx.is_some() {
+                    return Err(meta.error(ENDIAN_AND_CTX));
+                }
+                let endian: syn::LitStr = meta.value()?.parse()?;
+                res.endian = Some(match endian.value().as_str() {
+                    "be" => quote! { ::scroll::BE },
+                    "le" => quote! { ::scroll::LE },
+                    "native" => quote! { ::scroll::NATIVE },
+                    _ => {
+                        return Err(meta.error("endian must be one of \"be\", \"le\" or \"native\""))
+                    }
+                });
+                Ok(())
+            } else if meta.path.is_ident("count") {
+                let count: syn::LitStr = meta.value()?.parse()?;
+                res.count = Some(count.value());
+                Ok(())
+            } else if meta.path.is_ident("len_prefix") {
+                res.len_prefix = Some(meta.value()?.parse()?);
+                Ok(())
+            } else if meta.path.is_ident("ctx") {
+                if res.endian.is_some() {
+                    return Err(meta.error(ENDIAN_AND_CTX));
+                }
+                res.ctx = Some(parse_quoted_or(meta.value()?)?);
+                Ok(())
+            } else if meta.path.is_ident("bits") {
+                let bits: syn::LitInt = meta.value()?.parse()?;
+                match bits.base10_parse()? {
+                    0 => Err(meta.error("a bit field must have at least one bit")),
+                    bits => {
+                        res.bits = Some(bits);
+                        Ok(())
+                    }
+                }
+            } else if meta.path.is_ident("align") {
+                res.align = Some(parse_align(&meta)?);
+                Ok(())
+            } else if meta.path.is_ident("pad") {
+                res.pad = Some(parse_pad(&meta)?);
+                Ok(())
+            } else if meta.path.is_ident("assert") {
+                res.assert = Some(parse_quoted_or(meta.value()?)?);
+                Ok(())
+            } else if meta.path.is_ident("const") {
+                res.constant = Some(parse_quoted_or(meta.value()?)?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported scroll attribute"))
+            }
+        });
+        if res.constant.is_some() && res.is_variable() {
+            panic!("a Vec field can not have a const value");
+        }
+        if res.count.is_some() && res.len_prefix.is_some() {
+            panic!("a field can not have both a count and a len_prefix");
+        }
+        if res.bits.is_some() && (res.is_variable() || res.ctx.is_some() || res.endian.is_some()) {
+            panic!("a bit field can not have a count, len_prefix, ctx or endian");
+        }
+        res
+    }
+
+    /// Whether the field is a `Vec` whose length is only known at runtime.
+    pub fn is_variable(&self) -> bool {
+        self.count.is_some() || self.len_prefix.is_some()
+    }
+
+    /// The context the field (or each element of a `Vec` field) is read and written with, given
+    /// the name of the outer context.
+    pub fn value_ctx(&self, ctx: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        match self.ctx {
+            Some(ref expr) => quote! { (#expr) },
+            None => self.prefix_ctx(ctx),
+        }
+    }
+
+    /// The context the length prefix of a `Vec` field is read and written with.
+    pub fn prefix_ctx(&self, ctx: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        self.endian.clone().unwrap_or(ctx)
+    }
+}
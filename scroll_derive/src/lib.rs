@@ -1,12 +1,432 @@
 #![recursion_limit = "1024"]
 
 extern crate proc_macro;
-use proc_macro2;
 use quote::quote;
 
 use proc_macro::TokenStream;
 
-fn impl_field(ident: &proc_macro2::TokenStream, ty: &syn::Type) -> proc_macro2::TokenStream {
+mod attr;
+
+use attr::{ContainerAttrs, FieldAttrs, VariantAttrs};
+
+fn field_ident(i: usize, f: &syn::Field) -> proc_macro2::TokenStream {
+    f.ident.as_ref().map(|i| quote! {#i}).unwrap_or({
+        let t = proc_macro2::Literal::usize_unsuffixed(i);
+        quote! {#t}
+    })
+}
+
+fn generic_names(generics: &syn::Generics) -> proc_macro2::TokenStream {
+    let gl = &generics.lt_token;
+    let gp = &generics.params;
+    let gg = &generics.gt_token;
+    let gn = gp.iter().map(|param: &syn::GenericParam| match param {
+        syn::GenericParam::Type(ref t) => {
+            let ident = &t.ident;
+            quote! { #ident }
+        }
+        p => quote! { #p },
+    });
+    quote! { #gl #( #gn ),* #gg }
+}
+
+fn variant_fields(
+    variant: &syn::Variant,
+) -> Option<&syn::punctuated::Punctuated<syn::Field, syn::Token![,]>> {
+    match variant.fields {
+        syn::Fields::Named(ref fields) => Some(&fields.named),
+        syn::Fields::Unnamed(ref fields) => Some(&fields.unnamed),
+        syn::Fields::Unit => None,
+    }
+}
+
+/// The type of an enum's discriminant tag, as given by `#[scroll(tag = ty)]`.
+fn enum_tag_type(name: &syn::Ident, container: &ContainerAttrs) -> syn::Type {
+    if container.has_layout() {
+        panic!(
+            "align, pad, repr_c and magic can only be used on structs, not on enum {}",
+            name
+        );
+    }
+    match container.tag {
+        Some(ref ty) => ty.clone(),
+        None => panic!(
+            "enum {} requires a #[scroll(tag = <integer type>)] attribute",
+            name
+        ),
+    }
+}
+
+/// Declares one `const` per variant holding its tag value, so that tags can be used as patterns.
+fn enum_tag_consts(
+    data: &syn::DataEnum,
+    tag_ty: &syn::Type,
+) -> (Vec<proc_macro2::Ident>, proc_macro2::TokenStream) {
+    let mut names = Vec::new();
+    let mut consts = Vec::new();
+    for (i, variant) in data.variants.iter().enumerate() {
+        let value = match (
+            VariantAttrs::parse(&variant.attrs).tag,
+            &variant.discriminant,
+        ) {
+            (Some(value), _) => value,
+            (None, Some((_, value))) => value.clone(),
+            (None, None) => panic!(
+                "variant {} requires a #[scroll(tag = <value>)] attribute",
+                variant.ident
+            ),
+        };
+        let name = quote::format_ident!("__SCROLL_TAG_{}", i);
+        consts.push(quote! { const #name: #tag_ty = #value; });
+        names.push(name);
+    }
+    (names, quote! { #(#consts)* })
+}
+
+/// The local variable a field is read into before the value is constructed.
+fn field_local(i: usize, f: &syn::Field) -> proc_macro2::Ident {
+    match f.ident {
+        Some(ref ident) => quote::format_ident!("__{}", ident),
+        None => quote::format_ident!("__{}", i),
+    }
+}
+
+/// Finds the field called `name` among `fields`, tuple fields being named by their index.
+fn find_field<'f>(
+    fields: impl Iterator<Item = &'f syn::Field>,
+    name: &str,
+) -> Option<(usize, &'f syn::Field)> {
+    fields.enumerate().find(|(i, f)| match f.ident {
+        Some(ref ident) => ident == name,
+        None => i.to_string() == name,
+    })
+}
+
+/// Where the bit field `i` is in its run of consecutive bit fields: whether it starts the run, and
+/// the total number of bits in the run if it ends it. A run takes up whole bytes.
+fn bit_run(
+    fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+    i: usize,
+) -> (bool, Option<u32>) {
+    let bits = |f: &syn::Field| FieldAttrs::parse(&f.attrs).bits;
+    let fields: Vec<_> = fields.iter().collect();
+    let first = i == 0 || bits(fields[i - 1]).is_none();
+    let last = fields.get(i + 1).and_then(|f| bits(f)).is_none();
+    let total = if last {
+        Some(fields[..=i].iter().rev().map_while(|f| bits(f)).sum())
+    } else {
+        None
+    };
+    (first, total)
+}
+
+/// `pad` bytes followed by as many as it takes to align `offset` plus those to `align`, or `None`
+/// if there is neither.
+fn padding(
+    pad: Option<usize>,
+    align: Option<proc_macro2::TokenStream>,
+    offset: &proc_macro2::TokenStream,
+) -> Option<proc_macro2::TokenStream> {
+    let align = align.map(|align| {
+        let offset = match pad {
+            Some(pad) => quote! { (#offset + #pad) },
+            None => quote! { #offset },
+        };
+        quote! {
+            {
+                let __align: usize = #align;
+                (__align - #offset % __align) % __align
+            }
+        }
+    });
+    match (pad, align) {
+        (Some(pad), Some(align)) => Some(quote! { (#pad + #align) }),
+        (Some(pad), None) => Some(quote! { #pad }),
+        (None, align) => align,
+    }
+}
+
+/// The alignment of a field of type `ty` with `repr_c`: the size of a primitive, and that of the
+/// elements of an array, so that the layout is the same on every target; other types, like nested
+/// structs, are aligned as on the target being built for.
+fn repr_c_align(ty: &syn::Type) -> proc_macro2::TokenStream {
+    const PRIMITIVES: &[(&str, usize)] = &[
+        ("u8", 1),
+        ("i8", 1),
+        ("bool", 1),
+        ("u16", 2),
+        ("i16", 2),
+        ("u32", 4),
+        ("i32", 4),
+        ("f32", 4),
+        ("char", 4),
+        ("u64", 8),
+        ("i64", 8),
+        ("f64", 8),
+        ("u128", 16),
+        ("i128", 16),
+    ];
+    match ty {
+        syn::Type::Array(array) => repr_c_align(&array.elem),
+        syn::Type::Group(group) => repr_c_align(&group.elem),
+        syn::Type::Path(path) if path.qself.is_none() => {
+            match PRIMITIVES.iter().find(|(p, _)| path.path.is_ident(p)) {
+                Some((_, size)) => quote! { #size },
+                None => quote! { ::scroll::export::mem::align_of::<#ty>() },
+            }
+        }
+        _ => quote! { ::scroll::export::mem::align_of::<#ty>() },
+    }
+}
+
+/// The number of padding bytes in front of field `i` if it would otherwise be at `offset`: its
+/// `pad` bytes, then up to its `align`ment. With `repr_c`, fields other than bit fields are
+/// aligned to [`repr_c_align`] unless they have an `align` of their own.
+fn field_padding(
+    fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+    i: usize,
+    repr_c: bool,
+    offset: &proc_macro2::TokenStream,
+) -> Option<proc_macro2::TokenStream> {
+    let f = &fields[i];
+    let attrs = FieldAttrs::parse(&f.attrs);
+    let has_padding = attrs.align.is_some() || attrs.pad.is_some();
+    if attrs.bits.is_some() && has_padding && !bit_run(fields, i).0 {
+        panic!("only the first of consecutive bit fields can have an align or pad");
+    }
+    let align = match attrs.align {
+        Some(align) => Some(quote! { #align }),
+        None if repr_c && attrs.is_variable() => {
+            panic!("repr_c can not be used for types with variable-length fields")
+        }
+        None if repr_c && attrs.bits.is_none() => Some(repr_c_align(&f.ty)),
+        None => None,
+    };
+    padding(attrs.pad, align, offset)
+}
+
+/// The number of padding bytes at the end of a struct otherwise ending at `offset`: its `pad`
+/// bytes, then up to its `align`ment, which with `repr_c` is at least that of each of its fields.
+fn struct_padding(
+    fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+    container: &ContainerAttrs,
+    offset: &proc_macro2::TokenStream,
+) -> Option<proc_macro2::TokenStream> {
+    let mut aligns: Vec<_> = container
+        .align
+        .iter()
+        .map(|align| quote! { #align })
+        .collect();
+    if container.repr_c {
+        aligns.extend(
+            fields
+                .iter()
+                .filter(|f| FieldAttrs::parse(&f.attrs).bits.is_none())
+                .map(|f| repr_c_align(&f.ty)),
+        );
+    }
+    let align = aligns
+        .into_iter()
+        .reduce(|a, b| quote! { ::scroll::export::cmp::max(#a, #b) });
+    padding(container.pad, align, offset)
+}
+
+/// Skips `pad` bytes of padding when reading, failing if the source ends before them.
+fn read_padding(
+    pad: Option<proc_macro2::TokenStream>,
+    in_field: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    match pad {
+        // in a block, so that the locals don't shadow those of the fields
+        Some(pad) => quote! {{
+            let __pad = #pad;
+            let __len = src.len().saturating_sub(*offset);
+            if __pad > __len {
+                return Err(::scroll::Error::TooBig { size: __pad, len: __len }.at(*offset) #in_field);
+            }
+            *offset += __pad;
+        }},
+        None => quote! {},
+    }
+}
+
+/// Writes `pad` bytes of padding as zeros, failing if the destination ends before them.
+fn write_padding(pad: Option<proc_macro2::TokenStream>) -> proc_macro2::TokenStream {
+    match pad {
+        Some(pad) => quote! {{
+            let __pad = #pad;
+            let __len = dst.len().saturating_sub(*offset);
+            if __pad > __len {
+                return Err(::scroll::Error::TooBig { size: __pad, len: __len });
+            }
+            dst[*offset..*offset + __pad].fill(0);
+            *offset += __pad;
+        }},
+        None => quote! {},
+    }
+}
+
+/// Checks the field read into `local` against its `const` value and its `assert`ion, which can
+/// refer to the fields read so far; fails with a `BadInput` of `size` bytes at `start` otherwise.
+fn field_check(
+    fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+    attrs: &FieldAttrs,
+    local: &syn::Ident,
+    in_field: &proc_macro2::TokenStream,
+    start: proc_macro2::TokenStream,
+    size: proc_macro2::TokenStream,
+) -> Option<proc_macro2::TokenStream> {
+    let mut checks = Vec::new();
+    if let Some(ref value) = attrs.constant {
+        checks.push((
+            quote! { #local == (#value) },
+            quote! { concat!("expected the constant ", stringify!(#value)) },
+        ));
+    }
+    if let Some(ref assert) = attrs.assert {
+        let cond = replace_self_fields(quote! { #assert }, &|name| {
+            let (j, f) = find_field(fields.iter(), name)
+                .unwrap_or_else(|| panic!("assertion refers to unknown field {}", name));
+            let local = field_local(j, f);
+            quote! { #local }
+        });
+        checks.push((
+            cond,
+            quote! { concat!("assertion failed: ", stringify!(#assert)) },
+        ));
+    }
+    if checks.is_empty() {
+        return None;
+    }
+    let checks = checks.into_iter().map(|(cond, msg)| {
+        quote! {
+            if !(#cond) {
+                return Err(::scroll::Error::BadInput { size: #size, msg: #msg }.at(#start) #in_field);
+            }
+        }
+    });
+    Some(quote! { #(#checks)* })
+}
+
+/// The value written for a field: its `const` value if it has one, and `place` otherwise.
+fn field_value(
+    attrs: &FieldAttrs,
+    ty: &syn::Type,
+    place: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    match attrs.constant {
+        Some(ref value) => quote! { ({ let __value: #ty = #value; __value }) },
+        None => place,
+    }
+}
+
+/// Adds up `sizes`, the sizes of the fields, to `start`, along with the padding in front of each
+/// field and the padding at the end of a struct given its `container` attributes.
+fn sum_sizes(
+    fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+    container: Option<&ContainerAttrs>,
+    start: proc_macro2::TokenStream,
+    sizes: Vec<proc_macro2::TokenStream>,
+) -> proc_macro2::TokenStream {
+    let size = quote! { __size };
+    let repr_c = matches!(container, Some(container) if container.repr_c);
+    let steps = sizes.iter().enumerate().map(|(i, field_size)| {
+        let pad = field_padding(fields, i, repr_c, &size).map(|pad| quote! { __size += #pad; });
+        quote! {
+            #pad
+            __size += #field_size;
+        }
+    });
+    let tail = container
+        .and_then(|container| struct_padding(fields, container, &size))
+        .map(|pad| quote! { __size += #pad; });
+    quote! {
+        {
+            let mut __size: usize = #start;
+            #(#steps)*
+            #tail
+            __size
+        }
+    }
+}
+
+/// Replaces every `self.field` in `tokens` by `place(field)`, so that the context expression of a
+/// field can refer to other fields of the value being read or written.
+fn replace_self_fields<F>(tokens: proc_macro2::TokenStream, place: &F) -> proc_macro2::TokenStream
+where
+    F: Fn(&str) -> proc_macro2::TokenStream,
+{
+    use proc_macro2::{Group, TokenTree};
+    let tokens: Vec<_> = tokens.into_iter().collect();
+    let mut res = proc_macro2::TokenStream::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        match (&tokens[i], tokens.get(i + 1), tokens.get(i + 2)) {
+            (
+                TokenTree::Ident(this),
+                Some(TokenTree::Punct(dot)),
+                Some(field @ TokenTree::Ident(_)) | Some(field @ TokenTree::Literal(_)),
+            ) if this == "self" && dot.as_char() == '.' => {
+                res.extend(place(&field.to_string()));
+                i += 3;
+                continue;
+            }
+            (TokenTree::Group(group), _, _) => {
+                let mut new = Group::new(
+                    group.delimiter(),
+                    replace_self_fields(group.stream(), place),
+                );
+                new.set_span(group.span());
+                res.extend(Some(TokenTree::Group(new)));
+            }
+            (tt, _, _) => res.extend(Some(tt.clone())),
+        }
+        i += 1;
+    }
+    res
+}
+
+/// The context expression of a field, with references to other fields resolved through `place`.
+fn field_ctx<F>(
+    fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+    attrs: &FieldAttrs,
+    place: &F,
+) -> proc_macro2::TokenStream
+where
+    F: Fn(usize, &syn::Field) -> proc_macro2::TokenStream,
+{
+    replace_self_fields(attrs.value_ctx(quote! { ctx }), &|name| {
+        let (j, f) = find_field(fields.iter(), name)
+            .unwrap_or_else(|| panic!("context refers to unknown field {}", name));
+        place(j, f)
+    })
+}
+
+/// Reads the elements of a `Vec` field with a count of `count` elements; `in_field` records the
+/// field in the path of errors.
+fn impl_vec_field(
+    ty: &syn::Type,
+    ctx: &proc_macro2::TokenStream,
+    count: proc_macro2::TokenStream,
+    in_field: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    quote! {
+        {
+            let __count = #count;
+            let mut __tmp = <#ty>::with_capacity(::scroll::export::cmp::min(__count, src.len()));
+            for __i in 0..__count {
+                __tmp.push(src.gread_with(offset, #ctx).map_err(|e| ::scroll::Error::from(e).in_element(__i) #in_field)?);
+            }
+            __tmp
+        }
+    }
+}
+
+fn impl_field(
+    ty: &syn::Type,
+    ctx: &proc_macro2::TokenStream,
+    in_field: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
     match *ty {
         syn::Type::Array(ref array) => match array.len {
             syn::Expr::Lit(syn::ExprLit {
@@ -15,74 +435,282 @@ fn impl_field(ident: &proc_macro2::TokenStream, ty: &syn::Type) -> proc_macro2::
             }) => {
                 let size = int.base10_parse::<usize>().unwrap();
                 quote! {
-                    #ident: { let mut __tmp: #ty = [0u8.into(); #size]; src.gread_inout_with(offset, &mut __tmp, ctx)?; __tmp }
+                    {
+                        let mut __tmp: #ty = [0u8.into(); #size];
+                        src.gread_inout_with(offset, &mut __tmp, #ctx).map_err(|e| ::scroll::Error::from(e) #in_field)?;
+                        __tmp
+                    }
                 }
             }
             _ => panic!("Pread derive with bad array constexpr"),
         },
-        syn::Type::Group(ref group) => impl_field(ident, &group.elem),
+        syn::Type::Group(ref group) => impl_field(&group.elem, ctx, in_field),
+        // takes up no bytes, so there is nothing to read, even at the end of the buffer
+        syn::Type::Tuple(ref tuple) if tuple.elems.is_empty() => quote! { () },
         _ => {
             quote! {
-                #ident: src.gread_with::<#ty>(offset, ctx)?
+                src.gread_with::<#ty>(offset, #ctx).map_err(|e| ::scroll::Error::from(e) #in_field)?
             }
         }
     }
 }
 
-fn impl_struct(
-    name: &syn::Ident,
+/// Reads each field into a local; returns the statements doing so and the field initializers
+/// constructing the value from those locals. Errors are recorded to have occurred in the field of
+/// `ty_name`.
+fn impl_fields(
+    ty_name: &proc_macro2::TokenStream,
     fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
-    generics: &syn::Generics,
-) -> proc_macro2::TokenStream {
-    let items: Vec<_> = fields
+    repr_c: bool,
+) -> (Vec<proc_macro2::TokenStream>, Vec<proc_macro2::TokenStream>) {
+    fields
         .iter()
         .enumerate()
         .map(|(i, f)| {
-            let ident = &f.ident.as_ref().map(|i| quote! {#i}).unwrap_or({
-                let t = proc_macro2::Literal::usize_unsuffixed(i);
-                quote! {#t}
+            let attrs = FieldAttrs::parse(&f.attrs);
+            let ctx = field_ctx(fields, &attrs, &|j, f| {
+                let local = field_local(j, f);
+                quote! { #local }
             });
             let ty = &f.ty;
-            impl_field(ident, ty)
+            let ident = field_ident(i, f);
+            let field_name = ident.to_string();
+            let in_field = quote! { .in_field(#ty_name, #field_name) };
+            let local = field_local(i, f);
+            let padding = read_padding(
+                field_padding(fields, i, repr_c, &quote! { *offset }),
+                &in_field,
+            );
+            if let Some(bits) = attrs.bits {
+                let (first, last) = bit_run(fields, i);
+                let start = if first {
+                    quote! {
+                        let mut __bits = ::scroll::BitReader::new(
+                            src.get(*offset..).unwrap_or_default(),
+                            ::scroll::BitOrder::MsbFirst,
+                        );
+                    }
+                } else {
+                    quote! {}
+                };
+                let end = if last.is_some() {
+                    quote! { *offset += __bits.align(); }
+                } else {
+                    quote! {}
+                };
+                let check = field_check(
+                    fields,
+                    &attrs,
+                    &local,
+                    &in_field,
+                    quote! { *offset },
+                    quote! { ::scroll::export::mem::size_of::<#ty>() },
+                );
+                let read = quote! {
+                    #padding
+                    #start
+                    let #local = __bits.read_bits::<#ty>(#bits)
+                        .map_err(|e| e.at(*offset) #in_field)?;
+                    #check
+                    #end
+                };
+                return (read, quote! { #ident: #local });
+            }
+            let value = if let Some(ref name) = attrs.count {
+                let (j, count) = find_field(fields.iter().take(i), name).unwrap_or_else(|| {
+                    panic!("count field {} must precede the Vec it counts", name)
+                });
+                let count = field_local(j, count);
+                impl_vec_field(ty, &ctx, quote! { #count as usize }, &in_field)
+            } else if let Some(ref prefix) = attrs.len_prefix {
+                let prefix_ctx = attrs.prefix_ctx(quote! { ctx });
+                impl_vec_field(
+                    ty,
+                    &ctx,
+                    quote! {
+                        src.gread_with::<#prefix>(offset, #prefix_ctx)
+                            .map_err(|e| ::scroll::Error::from(e) #in_field)? as usize
+                    },
+                    &in_field,
+                )
+            } else {
+                impl_field(ty, &ctx, &in_field)
+            };
+            // hygienic, so that it can't shadow the locals the assertion refers to
+            let start = proc_macro2::Ident::new("__start", proc_macro2::Span::mixed_site());
+            let check = field_check(
+                fields,
+                &attrs,
+                &local,
+                &in_field,
+                quote! { #start },
+                quote! { *offset - #start },
+            );
+            let read = match check {
+                Some(check) => quote! {
+                    #padding
+                    let #start = *offset;
+                    let #local = #value;
+                    #check
+                },
+                None => quote! {
+                    #padding
+                    let #local = #value;
+                },
+            };
+            (read, quote! { #ident: #local })
         })
-        .collect();
+        .unzip()
+}
 
-    let gl = &generics.lt_token;
+/// The lifetime of the source buffer along with the parameters of the impl: the first lifetime
+/// parameter of the type if it has one, so that borrowed fields can point into the buffer, and a
+/// fresh `'a` otherwise.
+fn source_lifetime(
+    generics: &syn::Generics,
+) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
     let gp = &generics.params;
-    let gg = &generics.gt_token;
-    let gn = gp.iter().map(|param: &syn::GenericParam| match param {
-        syn::GenericParam::Type(ref t) => {
-            let ident = &t.ident;
-            quote! { #ident }
+    match generics.lifetimes().next() {
+        Some(param) => {
+            let lt = &param.lifetime;
+            (quote! { #lt }, quote! { #gp })
+        }
+        None => (quote! { 'a }, quote! { 'a, #gp }),
+    }
+}
+
+fn try_from_ctx_bounds(
+    generics: &syn::Generics,
+    lt: &proc_macro2::TokenStream,
+    ctx_ty: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let gi = generics.type_params().map(|t| {
+        let ident = &t.ident;
+        quote! {
+            #ident : ::scroll::ctx::TryFromCtx<#lt, #ctx_ty> + ::std::convert::From<u8> + ::std::marker::Copy,
+            ::scroll::Error : ::std::convert::From<< #ident as ::scroll::ctx::TryFromCtx<#lt, #ctx_ty>>::Error>,
+            < #ident as ::scroll::ctx::TryFromCtx<#lt, #ctx_ty>>::Error : ::std::convert::From<scroll::Error> + ::scroll::Locate,
         }
-        p => quote! { #p },
     });
-    let gn = quote! { #gl #( #gn ),* #gg };
-    let gw = if !gp.is_empty() {
-        let gi = gp.iter().map(|param: &syn::GenericParam| match param {
-            syn::GenericParam::Type(ref t) => {
-                let ident = &t.ident;
-                quote! {
-                    #ident : ::scroll::ctx::TryFromCtx<'a, ::scroll::Endian> + ::std::convert::From<u8> + ::std::marker::Copy,
-                    ::scroll::Error : ::std::convert::From<< #ident as ::scroll::ctx::TryFromCtx<'a, ::scroll::Endian>>::Error>,
-                    < #ident as ::scroll::ctx::TryFromCtx<'a, ::scroll::Endian>>::Error : ::std::convert::From<scroll::Error>
+    quote! { #( #gi )* }
+}
+
+/// Checks that `src` starts with the magic of the struct `name`, if it has one, and reads past it;
+/// fails with a `BadInput` located at the struct otherwise.
+fn magic_check(name: &syn::Ident, container: &ContainerAttrs) -> Option<proc_macro2::TokenStream> {
+    container.magic.as_ref().map(|magic| {
+        quote! {{
+            let __magic: &[u8] = #magic;
+            match src.get(..__magic.len()) {
+                Some(bytes) if bytes == __magic => *offset += __magic.len(),
+                Some(_) => {
+                    return Err(::scroll::Error::BadInput {
+                        size: __magic.len(),
+                        msg: concat!("bad magic number for ", stringify!(#name)),
+                    }
+                    .in_type(stringify!(#name))
+                    .at(0));
                 }
-            },
-            p => quote! { #p }
-        });
-        quote! { #( #gi ),* , }
-    } else {
-        quote! {}
-    };
+                None => {
+                    return Err(::scroll::Error::TooBig {
+                        size: __magic.len(),
+                        len: src.len(),
+                    }
+                    .in_type(stringify!(#name))
+                    .at(0));
+                }
+            }
+        }}
+    })
+}
+
+fn impl_struct(
+    name: &syn::Ident,
+    fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+    container: &ContainerAttrs,
+    generics: &syn::Generics,
+) -> proc_macro2::TokenStream {
+    let (reads, items) = impl_fields(&quote! { stringify!(#name) }, fields, container.repr_c);
+    let tail = read_padding(
+        struct_padding(fields, container, &quote! { *offset }),
+        &quote! {},
+    );
+    let magic = magic_check(name, container);
+
+    let (lt, gp) = source_lifetime(generics);
+    let gn = generic_names(generics);
+    let ctx_ty = container.ctx_type();
+    let gw = try_from_ctx_bounds(generics, &lt, &ctx_ty);
 
     quote! {
-        impl<'a, #gp > ::scroll::ctx::TryFromCtx<'a, ::scroll::Endian> for #name #gn where #gw #name #gn : 'a {
+        impl< #gp > ::scroll::ctx::TryFromCtx<#lt, #ctx_ty> for #name #gn where #gw #name #gn : #lt {
             type Error = ::scroll::Error;
             #[inline]
-            fn try_from_ctx(src: &'a [u8], ctx: ::scroll::Endian) -> ::scroll::export::result::Result<(Self, usize), Self::Error> {
+            fn try_from_ctx(src: &#lt [u8], ctx: #ctx_ty) -> ::scroll::export::result::Result<(Self, usize), Self::Error> {
                 use ::scroll::Pread;
                 let offset = &mut 0;
-                let data  = Self { #(#items,)* };
+                #magic
+                #(#reads)*
+                #tail
+                let data = Self { #(#items,)* };
+                Ok((data, *offset))
+            }
+        }
+    }
+}
+
+fn impl_enum(
+    name: &syn::Ident,
+    data: &syn::DataEnum,
+    container: &ContainerAttrs,
+    generics: &syn::Generics,
+) -> proc_macro2::TokenStream {
+    let tag_ty = enum_tag_type(name, container);
+    let (tags, tag_consts) = enum_tag_consts(data, &tag_ty);
+    let arms: Vec<_> = data
+        .variants
+        .iter()
+        .zip(tags.iter())
+        .map(|(variant, tag)| {
+            let ident = &variant.ident;
+            let ty_name = quote! { concat!(stringify!(#name), "::", stringify!(#ident)) };
+            let (reads, items) = variant_fields(variant)
+                .map(|fields| impl_fields(&ty_name, fields, false))
+                .unwrap_or_default();
+            quote! {
+                #tag => {
+                    #(#reads)*
+                    Self::#ident { #(#items,)* }
+                }
+            }
+        })
+        .collect();
+
+    let (lt, gp) = source_lifetime(generics);
+    let gn = generic_names(generics);
+    let ctx_ty = container.ctx_type();
+    let tag_ctx = container.tag_ctx(quote! { ctx });
+    let gw = try_from_ctx_bounds(generics, &lt, &ctx_ty);
+
+    quote! {
+        impl< #gp > ::scroll::ctx::TryFromCtx<#lt, #ctx_ty> for #name #gn where #gw #name #gn : #lt {
+            type Error = ::scroll::Error;
+            #[inline]
+            fn try_from_ctx(src: &#lt [u8], ctx: #ctx_ty) -> ::scroll::export::result::Result<(Self, usize), Self::Error> {
+                use ::scroll::Pread;
+                #tag_consts
+                let offset = &mut 0;
+                let data = match src.gread_with::<#tag_ty>(offset, #tag_ctx)? {
+                    #(#arms,)*
+                    __tag => {
+                        return Err(::scroll::Error::BadTag {
+                            size: ::scroll::export::mem::size_of::<#tag_ty>(),
+                            tag: __tag as u64,
+                        }
+                        .in_type(stringify!(#name))
+                        .at(0));
+                    }
+                };
                 Ok((data, *offset))
             }
         }
@@ -92,26 +720,37 @@ fn impl_struct(
 fn impl_try_from_ctx(ast: &syn::DeriveInput) -> proc_macro2::TokenStream {
     let name = &ast.ident;
     let generics = &ast.generics;
+    let container = ContainerAttrs::parse(&ast.attrs);
     match ast.data {
         syn::Data::Struct(ref data) => match data.fields {
-            syn::Fields::Named(ref fields) => impl_struct(name, &fields.named, generics),
-            syn::Fields::Unnamed(ref fields) => impl_struct(name, &fields.unnamed, generics),
+            syn::Fields::Named(ref fields) => {
+                impl_struct(name, &fields.named, &container, generics)
+            }
+            syn::Fields::Unnamed(ref fields) => {
+                impl_struct(name, &fields.unnamed, &container, generics)
+            }
             _ => {
                 panic!("Pread can not be derived for unit structs")
             }
         },
-        _ => panic!("Pread can only be derived for structs"),
+        syn::Data::Enum(ref data) => impl_enum(name, data, &container, generics),
+        _ => panic!("Pread can only be derived for structs and enums"),
     }
 }
 
-#[proc_macro_derive(Pread)]
+#[proc_macro_derive(Pread, attributes(scroll))]
 pub fn derive_pread(input: TokenStream) -> TokenStream {
     let ast: syn::DeriveInput = syn::parse(input).unwrap();
     let gen = impl_try_from_ctx(&ast);
     gen.into()
 }
 
-fn impl_pwrite_field(ident: &proc_macro2::TokenStream, ty: &syn::Type) -> proc_macro2::TokenStream {
+/// Writes the field found at the place expression `place`.
+fn impl_pwrite_field(
+    place: &proc_macro2::TokenStream,
+    ty: &syn::Type,
+    ctx: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
     match ty {
         syn::Type::Array(ref array) => match array.len {
             syn::Expr::Lit(syn::ExprLit {
@@ -121,77 +760,158 @@ fn impl_pwrite_field(ident: &proc_macro2::TokenStream, ty: &syn::Type) -> proc_m
                 let size = int.base10_parse::<usize>().unwrap();
                 quote! {
                     for i in 0..#size {
-                        dst.gwrite_with(&self.#ident[i], offset, ctx)?;
+                        dst.gwrite_with(&#place[i], offset, #ctx)?;
                     }
                 }
             }
             _ => panic!("Pwrite derive with bad array constexpr"),
         },
-        syn::Type::Group(group) => impl_pwrite_field(ident, &group.elem),
+        syn::Type::Group(group) => impl_pwrite_field(place, &group.elem, ctx),
+        syn::Type::Tuple(tuple) if tuple.elems.is_empty() => quote! {},
         _ => {
             quote! {
-                dst.gwrite_with(&self.#ident, offset, ctx)?
+                dst.gwrite_with(&#place, offset, #ctx)?
             }
         }
     }
 }
 
-fn impl_try_into_ctx(
-    name: &syn::Ident,
-    fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
-    generics: &syn::Generics,
+/// Writes the elements of a `Vec` field found at `place`, preceded by its length if it has a
+/// `len_prefix`, or after checking it against its `count` field found at `count`.
+fn impl_pwrite_vec_field(
+    place: &proc_macro2::TokenStream,
+    attrs: &FieldAttrs,
+    ctx: &proc_macro2::TokenStream,
+    count: Option<proc_macro2::TokenStream>,
 ) -> proc_macro2::TokenStream {
-    let items: Vec<_> = fields
+    let len = if let Some(ref prefix) = attrs.len_prefix {
+        let prefix_ctx = attrs.prefix_ctx(quote! { ctx });
+        quote! {
+            let __len: #prefix = ::scroll::export::convert::TryFrom::try_from(#place.len())
+                .map_err(|_| ::scroll::Error::BadInput {
+                    size: #place.len(),
+                    msg: "vector is too long for its length prefix",
+                })?;
+            dst.gwrite_with(__len, offset, #prefix_ctx)?;
+        }
+    } else {
+        quote! {
+            if #count as usize != #place.len() {
+                return Err(::scroll::Error::BadInput {
+                    size: #place.len(),
+                    msg: "vector length does not match its count field",
+                });
+            }
+        }
+    };
+    quote! {
+        #len
+        for __item in #place.iter() {
+            dst.gwrite_with(__item, offset, #ctx)?;
+        }
+    }
+}
+
+/// Writes each field, where `place` gives the place expression a field is found at.
+fn impl_pwrite_fields<F>(
+    fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+    repr_c: bool,
+    place: F,
+) -> Vec<proc_macro2::TokenStream>
+where
+    F: Fn(usize, &syn::Field) -> proc_macro2::TokenStream,
+{
+    fields
         .iter()
         .enumerate()
         .map(|(i, f)| {
-            let ident = &f.ident.as_ref().map(|i| quote! {#i}).unwrap_or({
-                let t = proc_macro2::Literal::usize_unsuffixed(i);
-                quote! {#t}
-            });
-            let ty = &f.ty;
-            impl_pwrite_field(ident, ty)
+            let attrs = FieldAttrs::parse(&f.attrs);
+            let ctx = field_ctx(fields, &attrs, &place);
+            let padding = write_padding(field_padding(fields, i, repr_c, &quote! { *offset }));
+            if let Some(bits) = attrs.bits {
+                let (first, last) = bit_run(fields, i);
+                let place = field_value(&attrs, &f.ty, place(i, f));
+                let start = if first {
+                    quote! {
+                        let mut __bits = ::scroll::BitWriter::new(
+                            dst.get_mut(*offset..).unwrap_or_default(),
+                            ::scroll::BitOrder::MsbFirst,
+                        );
+                    }
+                } else {
+                    quote! {}
+                };
+                let end = if last.is_some() {
+                    quote! { *offset += __bits.align(); }
+                } else {
+                    quote! {}
+                };
+                return quote! {
+                    #padding
+                    #start
+                    __bits.write_bits(#place, #bits)?;
+                    #end
+                };
+            }
+            let write = if attrs.is_variable() {
+                let count = attrs.count.as_ref().map(|name| {
+                    let (j, count) = find_field(fields.iter().take(i), name).unwrap_or_else(|| {
+                        panic!("count field {} must precede the Vec it counts", name)
+                    });
+                    place(j, count)
+                });
+                impl_pwrite_vec_field(&place(i, f), &attrs, &ctx, count)
+            } else {
+                impl_pwrite_field(&field_value(&attrs, &f.ty, place(i, f)), &f.ty, &ctx)
+            };
+            quote! {
+                #padding
+                #write
+            }
         })
-        .collect();
+        .collect()
+}
 
+/// Generates the `&'a T` and `T` impls of `TryIntoCtx`; `body` writes `self: &'a T` into `dst`.
+fn impl_try_into_ctx_with(
+    name: &syn::Ident,
+    generics: &syn::Generics,
+    ctx_ty: &proc_macro2::TokenStream,
+    body: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
     let gl = &generics.lt_token;
     let gp = &generics.params;
     let gg = &generics.gt_token;
-    let gn = gp.iter().map(|param: &syn::GenericParam| match param {
-        syn::GenericParam::Type(ref t) => {
-            let ident = &t.ident;
-            quote! { #ident }
-        }
-        p => quote! { #p },
-    });
-    let gn = quote! { #gl #( #gn ),* #gg };
+    let gn = generic_names(generics);
     let gwref = if !gp.is_empty() {
-        let gi = gp.iter().map(|param: &syn::GenericParam| match param {
+        // lifetimes need no bounds, and are already declared
+        let gi = gp.iter().filter_map(|param: &syn::GenericParam| match param {
             syn::GenericParam::Type(ref t) => {
                 let ident = &t.ident;
-                quote! {
-                    &'a #ident : ::scroll::ctx::TryIntoCtx<::scroll::Endian>,
-                    ::scroll::Error: ::std::convert::From<<&'a #ident as ::scroll::ctx::TryIntoCtx<::scroll::Endian>>::Error>,
-                    <&'a #ident as ::scroll::ctx::TryIntoCtx<::scroll::Endian>>::Error: ::std::convert::From<scroll::Error>
-                }
+                Some(quote! {
+                    &'a #ident : ::scroll::ctx::TryIntoCtx<#ctx_ty>,
+                    ::scroll::Error: ::std::convert::From<<&'a #ident as ::scroll::ctx::TryIntoCtx<#ctx_ty>>::Error>,
+                    <&'a #ident as ::scroll::ctx::TryIntoCtx<#ctx_ty>>::Error: ::std::convert::From<scroll::Error>
+                })
             },
-            p => quote! { #p }
+            _ => None,
         });
         quote! { where #( #gi ),* }
     } else {
         quote! {}
     };
     let gw = if !gp.is_empty() {
-        let gi = gp.iter().map(|param: &syn::GenericParam| match param {
+        // lifetimes need no bounds, and are already declared
+        let gi = gp.iter().filter_map(|param: &syn::GenericParam| match param {
             syn::GenericParam::Type(ref t) => {
                 let ident = &t.ident;
-                quote! {
-                    #ident : ::scroll::ctx::TryIntoCtx<::scroll::Endian>,
-                    ::scroll::Error: ::std::convert::From<<#ident as ::scroll::ctx::TryIntoCtx<::scroll::Endian>>::Error>,
-                    <#ident as ::scroll::ctx::TryIntoCtx<::scroll::Endian>>::Error: ::std::convert::From<scroll::Error>
-                }
+                Some(quote! {
+                    #ident : ::scroll::ctx::TryIntoCtx<#ctx_ty>,
+                    ::scroll::Error: ::std::convert::From<<#ident as ::scroll::ctx::TryIntoCtx<#ctx_ty>>::Error>,
+                    <#ident as ::scroll::ctx::TryIntoCtx<#ctx_ty>>::Error: ::std::convert::From<scroll::Error>
+                })
             },
-            p => quote! { #p }
+            _ => None,
         });
         quote! { where Self: ::std::marker::Copy, #( #gi ),* }
     } else {
@@ -199,58 +919,170 @@ fn impl_try_into_ctx(
     };
 
     quote! {
-        impl<'a, #gp > ::scroll::ctx::TryIntoCtx<::scroll::Endian> for &'a #name #gn #gwref {
+        impl<'a, #gp > ::scroll::ctx::TryIntoCtx<#ctx_ty> for &'a #name #gn #gwref {
             type Error = ::scroll::Error;
             #[inline]
-            fn try_into_ctx(self, dst: &mut [u8], ctx: ::scroll::Endian) -> ::scroll::export::result::Result<usize, Self::Error> {
+            fn try_into_ctx(self, dst: &mut [u8], ctx: #ctx_ty) -> ::scroll::export::result::Result<usize, Self::Error> {
                 use ::scroll::Pwrite;
                 let offset = &mut 0;
-                #(#items;)*;
+                #body
                 Ok(*offset)
             }
         }
 
-        impl #gl #gp #gg ::scroll::ctx::TryIntoCtx<::scroll::Endian> for #name #gn #gw {
+        impl #gl #gp #gg ::scroll::ctx::TryIntoCtx<#ctx_ty> for #name #gn #gw {
             type Error = ::scroll::Error;
             #[inline]
-            fn try_into_ctx(self, dst: &mut [u8], ctx: ::scroll::Endian) -> ::scroll::export::result::Result<usize, Self::Error> {
+            fn try_into_ctx(self, dst: &mut [u8], ctx: #ctx_ty) -> ::scroll::export::result::Result<usize, Self::Error> {
                 (&self).try_into_ctx(dst, ctx)
             }
         }
     }
 }
 
+fn impl_try_into_ctx(
+    name: &syn::Ident,
+    fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+    container: &ContainerAttrs,
+    generics: &syn::Generics,
+) -> proc_macro2::TokenStream {
+    let items = impl_pwrite_fields(fields, container.repr_c, |i, f| {
+        let ident = field_ident(i, f);
+        quote! { self.#ident }
+    });
+    let tail = write_padding(struct_padding(fields, container, &quote! { *offset }));
+    let magic = container.magic.as_ref().map(|magic| {
+        quote! {
+            dst.gwrite_with::<&[u8]>(#magic, offset, ())?;
+        }
+    });
+
+    impl_try_into_ctx_with(
+        name,
+        generics,
+        &container.ctx_type(),
+        quote! {
+            #magic
+            #(#items;)*;
+            #tail
+        },
+    )
+}
+
+fn impl_try_into_ctx_enum(
+    name: &syn::Ident,
+    data: &syn::DataEnum,
+    container: &ContainerAttrs,
+    generics: &syn::Generics,
+) -> proc_macro2::TokenStream {
+    let tag_ty = enum_tag_type(name, container);
+    let tag_ctx = container.tag_ctx(quote! { ctx });
+    let (tags, tag_consts) = enum_tag_consts(data, &tag_ty);
+    let arms: Vec<_> = data
+        .variants
+        .iter()
+        .zip(tags.iter())
+        .map(|(variant, tag)| {
+            let ident = &variant.ident;
+            let (bindings, items) = match variant_fields(variant) {
+                Some(fields) => {
+                    let bindings: Vec<_> = fields
+                        .iter()
+                        .enumerate()
+                        .map(|(i, f)| {
+                            let field = field_ident(i, f);
+                            let local = field_local(i, f);
+                            quote! { #field: #local }
+                        })
+                        .collect();
+                    let items = impl_pwrite_fields(fields, false, |i, f| {
+                        let local = field_local(i, f);
+                        quote! { (*#local) }
+                    });
+                    (bindings, items)
+                }
+                None => Default::default(),
+            };
+            quote! {
+                #name::#ident { #(#bindings,)* } => {
+                    dst.gwrite_with::<#tag_ty>(#tag, offset, #tag_ctx)?;
+                    #(#items;)*
+                }
+            }
+        })
+        .collect();
+
+    impl_try_into_ctx_with(
+        name,
+        generics,
+        &container.ctx_type(),
+        quote! {
+            #tag_consts
+            match self {
+                #(#arms)*
+            }
+        },
+    )
+}
+
 fn impl_pwrite(ast: &syn::DeriveInput) -> proc_macro2::TokenStream {
     let name = &ast.ident;
     let generics = &ast.generics;
+    let container = ContainerAttrs::parse(&ast.attrs);
     match ast.data {
         syn::Data::Struct(ref data) => match data.fields {
-            syn::Fields::Named(ref fields) => impl_try_into_ctx(name, &fields.named, generics),
-            syn::Fields::Unnamed(ref fields) => impl_try_into_ctx(name, &fields.unnamed, generics),
+            syn::Fields::Named(ref fields) => {
+                impl_try_into_ctx(name, &fields.named, &container, generics)
+            }
+            syn::Fields::Unnamed(ref fields) => {
+                impl_try_into_ctx(name, &fields.unnamed, &container, generics)
+            }
             _ => {
                 panic!("Pwrite can not be derived for unit structs")
             }
         },
-        _ => panic!("Pwrite can only be derived for structs"),
+        syn::Data::Enum(ref data) => impl_try_into_ctx_enum(name, data, &container, generics),
+        _ => panic!("Pwrite can only be derived for structs and enums"),
     }
 }
 
-#[proc_macro_derive(Pwrite)]
+#[proc_macro_derive(Pwrite, attributes(scroll))]
 pub fn derive_pwrite(input: TokenStream) -> TokenStream {
     let ast: syn::DeriveInput = syn::parse(input).unwrap();
     let gen = impl_pwrite(&ast);
     gen.into()
 }
 
-fn size_with(
-    name: &syn::Ident,
+/// The size of the bit field `i`: a run of bit fields takes up its bytes at its last field.
+fn bit_field_size(
     fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
-    generics: &syn::Generics,
+    i: usize,
 ) -> proc_macro2::TokenStream {
-    let items: Vec<_> = fields
+    match bit_run(fields, i).1 {
+        Some(total) => {
+            let size = total as usize / 8 + usize::from(total % 8 != 0);
+            quote! { #size }
+        }
+        None => quote! { 0 },
+    }
+}
+
+fn field_sizes(
+    fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+) -> Vec<proc_macro2::TokenStream> {
+    fields
         .iter()
-        .map(|f| {
+        .enumerate()
+        .map(|(i, f)| {
             let ty = &f.ty;
+            let attrs = FieldAttrs::parse(&f.attrs);
+            if attrs.is_variable() {
+                panic!("SizeWith can not be derived for types with variable-length fields");
+            }
+            if attrs.bits.is_some() {
+                return bit_field_size(fields, i);
+            }
+            let ctx = attrs.value_ctx(quote! { ctx });
             match *ty {
                 syn::Type::Array(ref array) => {
                     let elem = &array.elem;
@@ -261,7 +1093,7 @@ fn size_with(
                         }) => {
                             let size = int.base10_parse::<usize>().unwrap();
                             quote! {
-                                (#size * <#elem>::size_with(ctx))
+                                (#size * <#elem>::size_with(&#ctx))
                             }
                         }
                         _ => panic!("Pread derive with bad array constexpr"),
@@ -269,30 +1101,31 @@ fn size_with(
                 }
                 _ => {
                     quote! {
-                        <#ty>::size_with(ctx)
+                        <#ty>::size_with(&#ctx)
                     }
                 }
             }
         })
-        .collect();
+        .collect()
+}
 
+/// Generates the `SizeWith` impl; `body` computes the size given the context `ctx`.
+fn impl_size_with_for(
+    name: &syn::Ident,
+    generics: &syn::Generics,
+    ctx_ty: &proc_macro2::TokenStream,
+    body: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
     let gl = &generics.lt_token;
     let gp = &generics.params;
     let gg = &generics.gt_token;
-    let gn = gp.iter().map(|param: &syn::GenericParam| match param {
-        syn::GenericParam::Type(ref t) => {
-            let ident = &t.ident;
-            quote! { #ident }
-        }
-        p => quote! { #p },
-    });
-    let gn = quote! { #gl #( #gn ),* #gg };
+    let gn = generic_names(generics);
     let gw = if !gp.is_empty() {
         let gi = gp.iter().map(|param: &syn::GenericParam| match param {
             syn::GenericParam::Type(ref t) => {
                 let ident = &t.ident;
                 quote! {
-                    #ident : ::scroll::ctx::SizeWith<::scroll::Endian>
+                    #ident : ::scroll::ctx::SizeWith<#ctx_ty>
                 }
             }
             p => quote! { #p },
@@ -303,57 +1136,347 @@ fn size_with(
     };
 
     quote! {
-        impl #gl #gp #gg ::scroll::ctx::SizeWith<::scroll::Endian> for #name #gn #gw {
+        impl #gl #gp #gg ::scroll::ctx::SizeWith<#ctx_ty> for #name #gn #gw {
             #[inline]
-            fn size_with(ctx: &::scroll::Endian) -> usize {
-                0 #(+ #items)*
+            fn size_with(ctx: &#ctx_ty) -> usize {
+                let ctx = *ctx;
+                #body
             }
         }
     }
 }
 
+fn size_with(
+    name: &syn::Ident,
+    fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+    container: &ContainerAttrs,
+    generics: &syn::Generics,
+) -> proc_macro2::TokenStream {
+    let magic_len = container.magic_len();
+    let size = sum_sizes(
+        fields,
+        Some(container),
+        quote! { #magic_len },
+        field_sizes(fields),
+    );
+    impl_size_with_for(name, generics, &container.ctx_type(), size)
+}
+
+/// The size of a tagged enum is the size of its tag plus the size of its largest variant.
+fn size_with_enum(
+    name: &syn::Ident,
+    data: &syn::DataEnum,
+    container: &ContainerAttrs,
+    generics: &syn::Generics,
+) -> proc_macro2::TokenStream {
+    let tag_ty = enum_tag_type(name, container);
+    let tag_ctx = container.tag_ctx(quote! { ctx });
+    let tag_size = quote! { <#tag_ty>::size_with(&#tag_ctx) };
+    let variants: Vec<_> = data
+        .variants
+        .iter()
+        .map(|variant| match variant_fields(variant) {
+            Some(fields) => sum_sizes(fields, None, tag_size.clone(), field_sizes(fields)),
+            None => tag_size.clone(),
+        })
+        .collect();
+    impl_size_with_for(
+        name,
+        generics,
+        &container.ctx_type(),
+        quote! {
+            let mut size = #tag_size;
+            #(size = ::scroll::export::cmp::max(size, #variants);)*
+            size
+        },
+    )
+}
+
 fn impl_size_with(ast: &syn::DeriveInput) -> proc_macro2::TokenStream {
     let name = &ast.ident;
     let generics = &ast.generics;
+    let container = ContainerAttrs::parse(&ast.attrs);
     match ast.data {
         syn::Data::Struct(ref data) => match data.fields {
-            syn::Fields::Named(ref fields) => size_with(name, &fields.named, generics),
-            syn::Fields::Unnamed(ref fields) => size_with(name, &fields.unnamed, generics),
+            syn::Fields::Named(ref fields) => size_with(name, &fields.named, &container, generics),
+            syn::Fields::Unnamed(ref fields) => {
+                size_with(name, &fields.unnamed, &container, generics)
+            }
             _ => {
                 panic!("SizeWith can not be derived for unit structs")
             }
         },
-        _ => panic!("SizeWith can only be derived for structs"),
+        syn::Data::Enum(ref data) => size_with_enum(name, data, &container, generics),
+        _ => panic!("SizeWith can only be derived for structs and enums"),
     }
 }
 
-#[proc_macro_derive(SizeWith)]
+#[proc_macro_derive(SizeWith, attributes(scroll))]
 pub fn derive_sizewith(input: TokenStream) -> TokenStream {
     let ast: syn::DeriveInput = syn::parse(input).unwrap();
     let gen = impl_size_with(&ast);
     gen.into()
 }
 
+/// Whether a field of type `ty` always takes up the same number of bytes, so that it is measured
+/// with `SizeWith` instead of `MeasureWith`. A `char` only does with an `Endian`, so one with a
+/// `ctx` of its own, which may be a `CharCtx`, is measured with `MeasureWith`.
+fn is_fixed_size(ty: &syn::Type, attrs: &FieldAttrs) -> bool {
+    const PRIMITIVES: &[&str] = &[
+        "u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64", "u128", "i128", "f32", "f64", "bool",
+        "char", "usize", "isize",
+    ];
+    match ty {
+        syn::Type::Array(array) => is_fixed_size(&array.elem, attrs),
+        syn::Type::Group(group) => is_fixed_size(&group.elem, attrs),
+        syn::Type::Path(path) => {
+            path.qself.is_none()
+                && PRIMITIVES.iter().any(|p| path.path.is_ident(p))
+                && !(path.path.is_ident("char") && attrs.ctx.is_some())
+        }
+        _ => false,
+    }
+}
+
+/// Measures each field, where `place` gives the place expression a field is found at.
+fn field_measures<F>(
+    fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+    place: F,
+) -> Vec<proc_macro2::TokenStream>
+where
+    F: Fn(usize, &syn::Field) -> proc_macro2::TokenStream,
+{
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| {
+            let ty = &f.ty;
+            let attrs = FieldAttrs::parse(&f.attrs);
+            if attrs.bits.is_some() {
+                return bit_field_size(fields, i);
+            }
+            let ctx = field_ctx(fields, &attrs, &place);
+            let place = place(i, f);
+            let measure = quote! { ::scroll::ctx::MeasureWith::measure_with(&#place, &#ctx) };
+            if let Some(ref prefix) = attrs.len_prefix {
+                let prefix_ctx = attrs.prefix_ctx(quote! { ctx });
+                return quote! {
+                    (<#prefix as ::scroll::ctx::SizeWith<_>>::size_with(&#prefix_ctx) + #measure)
+                };
+            }
+            if attrs.count.is_some() {
+                return measure;
+            }
+            match ty {
+                syn::Type::Tuple(tuple) if tuple.elems.is_empty() => quote! { 0 },
+                syn::Type::Array(array) if is_fixed_size(ty, &attrs) => {
+                    let elem = &array.elem;
+                    quote! {
+                        (#place.len() * <#elem as ::scroll::ctx::SizeWith<_>>::size_with(&#ctx))
+                    }
+                }
+                syn::Type::Array(_) => quote! {
+                    #place
+                        .iter()
+                        .map(|__item| ::scroll::ctx::MeasureWith::measure_with(__item, &#ctx))
+                        .sum::<usize>()
+                },
+                _ if is_fixed_size(ty, &attrs) => {
+                    quote! { <#ty as ::scroll::ctx::SizeWith<_>>::size_with(&#ctx) }
+                }
+                _ => measure,
+            }
+        })
+        .collect()
+}
+
+/// Generates the `MeasureWith` impl; `body` measures `self` given the context `ctx`.
+fn impl_measure_with_for(
+    name: &syn::Ident,
+    generics: &syn::Generics,
+    ctx_ty: &proc_macro2::TokenStream,
+    body: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let gl = &generics.lt_token;
+    let gp = &generics.params;
+    let gg = &generics.gt_token;
+    let gn = generic_names(generics);
+    let gw = if !gp.is_empty() {
+        let gi = gp
+            .iter()
+            .filter_map(|param: &syn::GenericParam| match param {
+                syn::GenericParam::Type(ref t) => {
+                    let ident = &t.ident;
+                    Some(quote! {
+                        #ident : ::scroll::ctx::MeasureWith<#ctx_ty>
+                    })
+                }
+                _ => None,
+            });
+        quote! { where #( #gi ),* }
+    } else {
+        quote! {}
+    };
+
+    quote! {
+        impl #gl #gp #gg ::scroll::ctx::MeasureWith<#ctx_ty> for #name #gn #gw {
+            #[inline]
+            fn measure_with(&self, ctx: &#ctx_ty) -> usize {
+                let ctx = *ctx;
+                #body
+            }
+        }
+    }
+}
+
+fn measure_with(
+    name: &syn::Ident,
+    fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+    container: &ContainerAttrs,
+    generics: &syn::Generics,
+) -> proc_macro2::TokenStream {
+    let items = field_measures(fields, |i, f| {
+        let ident = field_ident(i, f);
+        quote! { self.#ident }
+    });
+    let magic_len = container.magic_len();
+    let size = sum_sizes(fields, Some(container), quote! { #magic_len }, items);
+    impl_measure_with_for(name, generics, &container.ctx_type(), size)
+}
+
+/// The size of a tagged enum is the size of its tag plus the size of the variant it holds.
+fn measure_with_enum(
+    name: &syn::Ident,
+    data: &syn::DataEnum,
+    container: &ContainerAttrs,
+    generics: &syn::Generics,
+) -> proc_macro2::TokenStream {
+    let tag_ty = enum_tag_type(name, container);
+    let tag_ctx = container.tag_ctx(quote! { ctx });
+    let tag_size = quote! { <#tag_ty as ::scroll::ctx::SizeWith<_>>::size_with(&#tag_ctx) };
+    let arms: Vec<_> = data
+        .variants
+        .iter()
+        .map(|variant| {
+            let ident = &variant.ident;
+            let (bindings, size) = match variant_fields(variant) {
+                Some(fields) => {
+                    let bindings: Vec<_> = fields
+                        .iter()
+                        .enumerate()
+                        .map(|(i, f)| {
+                            let field = field_ident(i, f);
+                            let local = field_local(i, f);
+                            quote! { #field: #local }
+                        })
+                        .collect();
+                    let items = field_measures(fields, |i, f| {
+                        let local = field_local(i, f);
+                        quote! { (*#local) }
+                    });
+                    (bindings, sum_sizes(fields, None, tag_size.clone(), items))
+                }
+                None => (Vec::new(), tag_size.clone()),
+            };
+            quote! {
+                #name::#ident { #(#bindings,)* } => #size,
+            }
+        })
+        .collect();
+    impl_measure_with_for(
+        name,
+        generics,
+        &container.ctx_type(),
+        quote! {
+            match self {
+                #(#arms)*
+            }
+        },
+    )
+}
+
+fn impl_measure_with(ast: &syn::DeriveInput) -> proc_macro2::TokenStream {
+    let name = &ast.ident;
+    let generics = &ast.generics;
+    let container = ContainerAttrs::parse(&ast.attrs);
+    match ast.data {
+        syn::Data::Struct(ref data) => match data.fields {
+            syn::Fields::Named(ref fields) => {
+                measure_with(name, &fields.named, &container, generics)
+            }
+            syn::Fields::Unnamed(ref fields) => {
+                measure_with(name, &fields.unnamed, &container, generics)
+            }
+            _ => {
+                panic!("MeasureWith can not be derived for unit structs")
+            }
+        },
+        syn::Data::Enum(ref data) => measure_with_enum(name, data, &container, generics),
+        _ => panic!("MeasureWith can only be derived for structs and enums"),
+    }
+}
+
+#[proc_macro_derive(MeasureWith, attributes(scroll))]
+pub fn derive_measurewith(input: TokenStream) -> TokenStream {
+    let ast: syn::DeriveInput = syn::parse(input).unwrap();
+    let gen = impl_measure_with(&ast);
+    gen.into()
+}
+
 fn impl_cread_struct(
     name: &syn::Ident,
     fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+    container: &ContainerAttrs,
     generics: &syn::Generics,
 ) -> proc_macro2::TokenStream {
+    // reading can't fail here, so the checks Pread makes are refused rather than skipped
+    let checked = container
+        .magic
+        .as_ref()
+        .map(|magic| quote! { #magic })
+        .or_else(|| {
+            fields.iter().find_map(|f| {
+                let attrs = FieldAttrs::parse(&f.attrs);
+                attrs.constant.or(attrs.assert).map(|expr| quote! { #expr })
+            })
+        });
+    if let Some(checked) = checked {
+        return syn::Error::new_spanned(
+            checked,
+            "IOread can not be derived for types with a magic, const or assert, as it can't \
+             fail reading them; derive Pread and read the type with ioread_try_with instead",
+        )
+        .to_compile_error();
+    }
+    let ctx_ty = container.ctx_type();
     let items: Vec<_> = fields.iter().enumerate().map(|(i, f)| {
         let ident = &f.ident.as_ref().map(|i|quote!{#i}).unwrap_or({let t = proc_macro2::Literal::usize_unsuffixed(i); quote!{#t}});
         let ty = &f.ty;
+        let attrs = FieldAttrs::parse(&f.attrs);
+        if attrs.is_variable() {
+            panic!("IOread can not be derived for types with variable-length fields");
+        }
+        if attrs.bits.is_some() {
+            panic!("IOread can not be derived for types with bit fields");
+        }
+        let ctx = field_ctx(fields, &attrs, &|_, _| {
+            panic!("IOread context expressions can not refer to other fields")
+        });
+        let padding = field_padding(fields, i, container.repr_c, &quote! { *offset })
+            .map(|pad| quote! { *offset += #pad; });
         match *ty {
             syn::Type::Array(ref array) => {
                 let arrty = &array.elem;
                 match array.len {
                     syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Int(ref int), ..}) => {
                         let size = int.base10_parse::<usize>().unwrap();
-                        let incr = quote! { ::scroll::export::mem::size_of::<#arrty>() };
+                        let incr = quote! { <#arrty as ::scroll::ctx::SizeWith<_>>::size_with(&#ctx) };
                         quote! {
                             #ident: {
+                                #padding
                                 let mut __tmp: #ty = [0u8.into(); #size];
                                 for i in 0..__tmp.len() {
-                                    __tmp[i] = src.cread_with(*offset, ctx);
+                                    __tmp[i] = src.cread_with(*offset, #ctx);
                                     *offset += #incr;
                                 }
                                 __tmp
@@ -364,9 +1487,9 @@ fn impl_cread_struct(
                 }
             },
             _ => {
-                let size = quote! { ::scroll::export::mem::size_of::<#ty>() };
+                let size = quote! { <#ty as ::scroll::ctx::SizeWith<_>>::size_with(&#ctx) };
                 quote! {
-                    #ident: { let res = src.cread_with::<#ty>(*offset, ctx); *offset += #size; res }
+                    #ident: { #padding let res = src.cread_with::<#ty>(*offset, #ctx); *offset += #size; res }
                 }
             }
         }
@@ -388,7 +1511,7 @@ fn impl_cread_struct(
             syn::GenericParam::Type(ref t) => {
                 let ident = &t.ident;
                 quote! {
-                    #ident : ::scroll::ctx::FromCtx<::scroll::Endian> + ::std::convert::From<u8> + ::std::marker::Copy
+                    #ident : ::scroll::ctx::FromCtx<#ctx_ty> + ::scroll::ctx::SizeWith<#ctx_ty> + ::std::convert::From<u8> + ::std::marker::Copy
                 }
             },
             p => quote! { #p }
@@ -399,9 +1522,9 @@ fn impl_cread_struct(
     };
 
     quote! {
-        impl #gl #gp #gg ::scroll::ctx::FromCtx<::scroll::Endian> for #name #gn #gw {
+        impl #gl #gp #gg ::scroll::ctx::FromCtx<#ctx_ty> for #name #gn #gw {
             #[inline]
-            fn from_ctx(src: &[u8], ctx: ::scroll::Endian) -> Self {
+            fn from_ctx(src: &[u8], ctx: #ctx_ty) -> Self {
                 use ::scroll::Cread;
                 let offset = &mut 0;
                 let data = Self { #(#items,)* };
@@ -414,10 +1537,15 @@ fn impl_cread_struct(
 fn impl_from_ctx(ast: &syn::DeriveInput) -> proc_macro2::TokenStream {
     let name = &ast.ident;
     let generics = &ast.generics;
+    let container = ContainerAttrs::parse(&ast.attrs);
     match ast.data {
         syn::Data::Struct(ref data) => match data.fields {
-            syn::Fields::Named(ref fields) => impl_cread_struct(name, &fields.named, generics),
-            syn::Fields::Unnamed(ref fields) => impl_cread_struct(name, &fields.unnamed, generics),
+            syn::Fields::Named(ref fields) => {
+                impl_cread_struct(name, &fields.named, &container, generics)
+            }
+            syn::Fields::Unnamed(ref fields) => {
+                impl_cread_struct(name, &fields.unnamed, &container, generics)
+            }
             _ => {
                 panic!("IOread can not be derived for unit structs")
             }
@@ -426,7 +1554,7 @@ fn impl_from_ctx(ast: &syn::DeriveInput) -> proc_macro2::TokenStream {
     }
 }
 
-#[proc_macro_derive(IOread)]
+#[proc_macro_derive(IOread, attributes(scroll))]
 pub fn derive_ioread(input: TokenStream) -> TokenStream {
     let ast: syn::DeriveInput = syn::parse(input).unwrap();
     let gen = impl_from_ctx(&ast);
@@ -436,8 +1564,10 @@ pub fn derive_ioread(input: TokenStream) -> TokenStream {
 fn impl_into_ctx(
     name: &syn::Ident,
     fields: &syn::punctuated::Punctuated<syn::Field, syn::Token![,]>,
+    container: &ContainerAttrs,
     generics: &syn::Generics,
 ) -> proc_macro2::TokenStream {
+    let ctx_ty = container.ctx_type();
     let items: Vec<_> = fields
         .iter()
         .enumerate()
@@ -447,21 +1577,38 @@ fn impl_into_ctx(
                 quote! {#t}
             });
             let ty = &f.ty;
-            let size = quote! { ::scroll::export::mem::size_of::<#ty>() };
+            let attrs = FieldAttrs::parse(&f.attrs);
+            if attrs.is_variable() {
+                panic!("IOwrite can not be derived for types with variable-length fields");
+            }
+            if attrs.bits.is_some() {
+                panic!("IOwrite can not be derived for types with bit fields");
+            }
+            let ctx = field_ctx(fields, &attrs, &|i, f| {
+                let ident = field_ident(i, f);
+                quote! { self.#ident }
+            });
+            let size = quote! { <#ty as ::scroll::ctx::SizeWith<_>>::size_with(&#ctx) };
+            let padding = field_padding(fields, i, container.repr_c, &quote! { *offset })
+                .map(|pad| iowrite_padding(&pad));
+            let value = field_value(&attrs, ty, quote! { self.#ident });
             match *ty {
                 syn::Type::Array(ref array) => {
                     let arrty = &array.elem;
                     quote! {
-                        let size = ::scroll::export::mem::size_of::<#arrty>();
-                        for i in 0..self.#ident.len() {
-                            dst.cwrite_with(self.#ident[i], *offset, ctx);
+                        #padding
+                        let size = <#arrty as ::scroll::ctx::SizeWith<_>>::size_with(&#ctx);
+                        let __array = #value;
+                        for i in 0..__array.len() {
+                            dst.cwrite_with(__array[i], *offset, #ctx);
                             *offset += size;
                         }
                     }
                 }
                 _ => {
                     quote! {
-                        dst.cwrite_with(self.#ident, *offset, ctx);
+                        #padding
+                        dst.cwrite_with(#value, *offset, #ctx);
                         *offset += #size;
                     }
                 }
@@ -484,7 +1631,7 @@ fn impl_into_ctx(
             syn::GenericParam::Type(ref t) => {
                 let ident = &t.ident;
                 quote! {
-                    #ident : ::scroll::ctx::IntoCtx<::scroll::Endian> + ::std::marker::Copy
+                    #ident : ::scroll::ctx::IntoCtx<#ctx_ty> + ::scroll::ctx::SizeWith<#ctx_ty> + ::std::marker::Copy
                 }
             }
             p => quote! { #p },
@@ -494,34 +1641,59 @@ fn impl_into_ctx(
         quote! {}
     };
     let gn = quote! { #gl #( #gn ),* #gg };
+    let tail =
+        struct_padding(fields, container, &quote! { *offset }).map(|pad| iowrite_padding(&pad));
+    let magic = container.magic.as_ref().map(|magic| {
+        quote! {{
+            let __magic: &[u8] = #magic;
+            dst[..__magic.len()].copy_from_slice(__magic);
+            *offset += __magic.len();
+        }}
+    });
 
     quote! {
-        impl<'a, #gp > ::scroll::ctx::IntoCtx<::scroll::Endian> for &'a #name #gn #gw {
+        impl<'a, #gp > ::scroll::ctx::IntoCtx<#ctx_ty> for &'a #name #gn #gw {
             #[inline]
-            fn into_ctx(self, dst: &mut [u8], ctx: ::scroll::Endian) {
+            fn into_ctx(self, dst: &mut [u8], ctx: #ctx_ty) {
                 use ::scroll::Cwrite;
                 let offset = &mut 0;
+                #magic
                 #(#items;)*;
-                ()
+                #tail
             }
         }
 
-        impl #gl #gp #gg ::scroll::ctx::IntoCtx<::scroll::Endian> for #name #gn #gw {
+        impl #gl #gp #gg ::scroll::ctx::IntoCtx<#ctx_ty> for #name #gn #gw {
             #[inline]
-            fn into_ctx(self, dst: &mut [u8], ctx: ::scroll::Endian) {
+            fn into_ctx(self, dst: &mut [u8], ctx: #ctx_ty) {
                 (&self).into_ctx(dst, ctx)
             }
         }
     }
 }
 
+/// Writes `pad` bytes of padding as zeros, panicking like `Cwrite` if the destination ends
+/// before them.
+fn iowrite_padding(pad: &proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+    quote! {{
+        let __pad = #pad;
+        dst[*offset..*offset + __pad].fill(0);
+        *offset += __pad;
+    }}
+}
+
 fn impl_iowrite(ast: &syn::DeriveInput) -> proc_macro2::TokenStream {
     let name = &ast.ident;
     let generics = &ast.generics;
+    let container = ContainerAttrs::parse(&ast.attrs);
     match ast.data {
         syn::Data::Struct(ref data) => match data.fields {
-            syn::Fields::Named(ref fields) => impl_into_ctx(name, &fields.named, generics),
-            syn::Fields::Unnamed(ref fields) => impl_into_ctx(name, &fields.unnamed, generics),
+            syn::Fields::Named(ref fields) => {
+                impl_into_ctx(name, &fields.named, &container, generics)
+            }
+            syn::Fields::Unnamed(ref fields) => {
+                impl_into_ctx(name, &fields.unnamed, &container, generics)
+            }
             _ => {
                 panic!("IOwrite can not be derived for unit structs")
             }
@@ -530,7 +1702,7 @@ fn impl_iowrite(ast: &syn::DeriveInput) -> proc_macro2::TokenStream {
     }
 }
 
-#[proc_macro_derive(IOwrite)]
+#[proc_macro_derive(IOwrite, attributes(scroll))]
 pub fn derive_iowrite(input: TokenStream) -> TokenStream {
     let ast: syn::DeriveInput = syn::parse(input).unwrap();
     let gen = impl_iowrite(&ast);
@@ -1,5 +1,5 @@
 use scroll::{Cread, Cwrite, Pread, Pwrite, LE};
-use scroll_derive::{IOread, IOwrite, Pread, Pwrite, SizeWith};
+use scroll_derive::{IOread, IOwrite, MeasureWith, Pread, Pwrite, SizeWith};
 
 use scroll::ctx::SizeWith;
 
@@ -211,3 +211,798 @@ fn test_newtype() {
     let written = bytes.pwrite_with(&data, 0, LE).unwrap();
     assert_eq!(written, size);
 }
+
+#[derive(Debug, PartialEq, Pread, Pwrite, SizeWith)]
+#[scroll(tag = u16)]
+enum Data10 {
+    #[scroll(tag = 0x10)]
+    Empty,
+    #[scroll(tag = 0x11)]
+    Point(u16, u16),
+    #[scroll(tag = 0x20)]
+    Named { id: u32, arr: [u8; 2] },
+}
+
+#[test]
+fn test_enum() {
+    use scroll::BE;
+
+    let bytes = [0x00, 0x11, 0xde, 0xad, 0xbe, 0xef];
+    let mut offset = 0;
+    let data: Data10 = bytes.gread_with(&mut offset, BE).unwrap();
+    assert_eq!(data, Data10::Point(0xdead, 0xbeef));
+    assert_eq!(offset, 6);
+
+    let bytes = [0x20, 0x00, 0xef, 0xbe, 0xad, 0xde, 0x01, 0x02];
+    let data: Data10 = bytes.pread_with(0, LE).unwrap();
+    assert_eq!(
+        data,
+        Data10::Named {
+            id: 0xdeadbeef,
+            arr: [1, 2]
+        }
+    );
+
+    let mut offset = 0;
+    let data: Data10 = [0x10, 0x00].gread_with(&mut offset, LE).unwrap();
+    assert_eq!(data, Data10::Empty);
+    assert_eq!(offset, 2);
+
+    let err = [0xff, 0x12u8, 0x00]
+        .pread_with::<Data10>(1, LE)
+        .unwrap_err();
+    assert_eq!(
+        err.kind(),
+        Some(scroll::Cause::BadTag { size: 2, tag: 0x12 })
+    );
+    assert_eq!(err.offset(), Some(1));
+    assert_eq!(err.path().unwrap().type_name(), Some("Data10"));
+    assert_eq!(
+        err.to_string(),
+        "unknown tag 0x12 (2) at offset 0x1 in Data10"
+    );
+
+    assert_eq!(Data10::size_with(&LE), 8);
+}
+
+#[test]
+fn test_enum_roundtrip() {
+    use scroll::BE;
+
+    let values = [
+        Data10::Empty,
+        Data10::Point(1, 2),
+        Data10::Named {
+            id: 0xdeadbeef,
+            arr: [3, 4],
+        },
+    ];
+    for value in values.iter() {
+        let mut bytes = [0u8; 8];
+        let written = bytes.pwrite_with(value, 0, BE).unwrap();
+        let mut read = 0;
+        let value2: Data10 = bytes.gread_with(&mut read, BE).unwrap();
+        assert_eq!(written, read);
+        assert_eq!(*value, value2);
+    }
+    let mut bytes = [0u8; 6];
+    bytes
+        .pwrite_with(Data10::Point(0xdead, 0xbeef), 0, BE)
+        .unwrap();
+    assert_eq!(bytes, [0x00, 0x11, 0xde, 0xad, 0xbe, 0xef]);
+}
+
+const DATA11_B: u8 = 2;
+
+#[derive(Debug, PartialEq, Pread, Pwrite, SizeWith)]
+#[scroll(tag = u8)]
+#[repr(u8)]
+enum Data11<T> {
+    A(T) = 1,
+    #[scroll(tag = DATA11_B)]
+    B,
+}
+
+#[test]
+fn test_enum_generic() {
+    let bytes = [0x01, 0xef, 0xbe];
+    let data: Data11<u16> = bytes.pread_with(0, LE).unwrap();
+    assert_eq!(data, Data11::A(0xbeef));
+    let data: Data11<u16> = [DATA11_B].pread_with(0, LE).unwrap();
+    assert_eq!(data, Data11::B);
+    let mut out = [0u8; 3];
+    assert_eq!(out.pwrite_with(&Data11::<u16>::B, 0, LE).unwrap(), 1);
+    assert_eq!(out[0], DATA11_B);
+    assert_eq!(Data11::<u16>::size_with(&LE), 3);
+}
+
+#[derive(Debug, PartialEq, Pread, Pwrite, IOread, IOwrite, SizeWith)]
+struct Data12 {
+    len: u16,
+    #[scroll(endian = "be")]
+    port: u16,
+    #[scroll(endian = "le")]
+    addr: [u16; 2],
+}
+
+#[test]
+fn test_field_endian() {
+    use scroll::BE;
+
+    let bytes = [0x00, 0x02, 0x1f, 0x90, 0x01, 0x00, 0x02, 0x00];
+    let data: Data12 = bytes.pread_with(0, BE).unwrap();
+    assert_eq!(data.len, 2);
+    assert_eq!(data.port, 8080);
+    assert_eq!(data.addr, [1, 2]);
+    let data2: Data12 = bytes.cread_with(0, BE);
+    assert_eq!(data, data2);
+
+    let mut out = [0u8; 8];
+    out.pwrite_with(&data, 0, LE).unwrap();
+    assert_eq!(out, [0x02, 0x00, 0x1f, 0x90, 0x01, 0x00, 0x02, 0x00]);
+    let mut out2 = [0u8; 8];
+    out2.cwrite_with(&data, 0, LE);
+    assert_eq!(out, out2);
+    assert_eq!(Data12::size_with(&LE), 8);
+}
+
+#[derive(Debug, PartialEq, Pread, Pwrite)]
+struct Data13 {
+    count: u32,
+    #[scroll(count = "count")]
+    entries: Vec<u16>,
+    #[scroll(len_prefix = u8)]
+    names: Vec<Data3>,
+}
+
+#[test]
+fn test_vec_fields() {
+    let bytes = [
+        0x02, 0x00, 0x00, 0x00, 0xef, 0xbe, 0xad, 0xde, 0x01, 0x04, 0x03, 0x02, 0x01,
+    ];
+    let mut offset = 0;
+    let data: Data13 = bytes.gread_with(&mut offset, LE).unwrap();
+    assert_eq!(offset, bytes.len());
+    assert_eq!(data.count, 2);
+    assert_eq!(data.entries, vec![0xbeef, 0xdead]);
+    assert_eq!(data.names, vec![Data3 { name: 0x01020304 }]);
+
+    let mut out = [0u8; 13];
+    let written = out.pwrite_with(&data, 0, LE).unwrap();
+    assert_eq!(written, bytes.len());
+    assert_eq!(out, bytes);
+
+    let data = Data13 {
+        count: 3,
+        entries: vec![1, 2],
+        names: vec![],
+    };
+    assert!(out.pwrite_with(&data, 0, LE).is_err());
+
+    // the count may not claim more elements than the input holds
+    let bytes = [0xff, 0xff, 0xff, 0xff, 0x01, 0x00];
+    assert!(bytes.pread_with::<Data13>(0, LE).is_err());
+}
+
+#[derive(Debug, PartialEq, Pread, Pwrite, MeasureWith)]
+#[scroll(tag = u8)]
+enum Data14 {
+    #[scroll(tag = 1)]
+    List(u8, #[scroll(count = "0")] Vec<u8>),
+    #[scroll(tag = 2)]
+    Prefixed {
+        #[scroll(len_prefix = u16, endian = "be")]
+        items: Vec<u16>,
+    },
+}
+
+#[test]
+fn test_vec_fields_enum() {
+    let bytes = [0x01, 0x02, 0xaa, 0xbb];
+    let data: Data14 = bytes.pread_with(0, LE).unwrap();
+    assert_eq!(data, Data14::List(2, vec![0xaa, 0xbb]));
+    let mut out = [0u8; 4];
+    out.pwrite_with(&data, 0, LE).unwrap();
+    assert_eq!(out, bytes);
+
+    let bytes = [0x02, 0x00, 0x01, 0x12, 0x34];
+    let data: Data14 = bytes.pread_with(0, LE).unwrap();
+    assert_eq!(
+        data,
+        Data14::Prefixed {
+            items: vec![0x1234]
+        }
+    );
+    let mut out = [0u8; 5];
+    out.pwrite_with(&data, 0, LE).unwrap();
+    assert_eq!(out, bytes);
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Context15 {
+    endian: scroll::Endian,
+}
+
+#[derive(Debug, PartialEq, Pread, Pwrite, SizeWith)]
+#[scroll(ctx = "Context15")]
+struct Header15 {
+    #[scroll(ctx = ctx.endian)]
+    magic: u16,
+    #[scroll(ctx = "ctx.endian")]
+    name_len: u8,
+}
+
+#[derive(Debug, PartialEq, Pread)]
+#[scroll(ctx = "Context15")]
+struct Data15<'b> {
+    header: Header15,
+    #[scroll(ctx = scroll::ctx::StrCtx::Length(self.header.name_len as usize))]
+    name: &'b str,
+    #[scroll(ctx = ctx.endian)]
+    value: u32,
+}
+
+#[test]
+fn test_custom_ctx() {
+    let bytes = [0xbe, 0xba, 0x03, b'f', b'o', b'o', 0x00, 0x00, 0x00, 0x2a];
+    let ctx = Context15 { endian: scroll::BE };
+    let data: Data15 = bytes.pread_with(0, ctx).unwrap();
+    assert_eq!(
+        data,
+        Data15 {
+            header: Header15 {
+                magic: 0xbeba,
+                name_len: 3
+            },
+            name: "foo",
+            value: 42,
+        }
+    );
+    assert_eq!(Header15::size_with(&ctx), 3);
+    let mut out = [0u8; 3];
+    out.pwrite_with(&data.header, 0, ctx).unwrap();
+    assert_eq!(out, bytes[..3]);
+}
+
+impl From<Context15> for scroll::Endian {
+    fn from(ctx: Context15) -> Self {
+        ctx.endian
+    }
+}
+
+#[derive(Debug, PartialEq, Pread, Pwrite, SizeWith)]
+#[scroll(tag = u16, ctx = "Context15")]
+enum Data15b {
+    #[scroll(tag = 1)]
+    Header(Header15),
+    #[scroll(tag = 2)]
+    Empty,
+}
+
+#[test]
+fn test_custom_ctx_enum() {
+    let bytes = [0x00, 0x01, 0xbe, 0xba, 0x03];
+    let ctx = Context15 { endian: scroll::BE };
+    let data: Data15b = bytes.pread_with(0, ctx).unwrap();
+    assert_eq!(
+        data,
+        Data15b::Header(Header15 {
+            magic: 0xbeba,
+            name_len: 3
+        })
+    );
+    assert_eq!(Data15b::size_with(&ctx), 5);
+    let mut out = [0u8; 5];
+    out.pwrite_with(&data, 0, ctx).unwrap();
+    assert_eq!(out, bytes);
+}
+
+#[derive(Debug, PartialEq, Pread, Pwrite)]
+#[scroll(tag = u8)]
+enum Data16 {
+    #[scroll(tag = 0)]
+    Value {
+        big: u8,
+        #[scroll(ctx = if self.big != 0 { scroll::BE } else { scroll::LE })]
+        value: u16,
+    },
+}
+
+#[test]
+fn test_ctx_from_field() {
+    for bytes in [[0x00, 0x00, 0x34, 0x12], [0x00, 0x01, 0x12, 0x34]] {
+        let data: Data16 = bytes.pread_with(0, LE).unwrap();
+        assert_eq!(
+            data,
+            Data16::Value {
+                big: bytes[1],
+                value: 0x1234
+            }
+        );
+        let mut out = [0u8; 4];
+        out.pwrite_with(&data, 0, LE).unwrap();
+        assert_eq!(out, bytes);
+    }
+}
+
+#[derive(Debug, PartialEq, Pread)]
+struct Section17 {
+    kind: u8,
+    name: u16,
+}
+
+#[derive(Debug, PartialEq, Pread)]
+struct Header17 {
+    magic: u32,
+    #[scroll(len_prefix = u8)]
+    sections: Vec<Section17>,
+}
+
+#[test]
+fn test_error_path() {
+    let bytes = [
+        0xff, 0xff, 0xff, 0xff, 0x7f, 0x45, 0x4c, 0x46, 0x02, 0x01, 0x00, 0x01, 0x02, 0x00,
+    ];
+    let err = bytes.pread_with::<Header17>(4, LE).unwrap_err();
+    assert_eq!(err.offset(), Some(13));
+    assert_eq!(err.path().unwrap().to_string(), "Header17.sections[1].name");
+    assert_eq!(
+        err.to_string(),
+        "type is too big (2) for 1 at offset 0xd in Header17.sections[1].name"
+    );
+
+    let err = [0x02u8, 0x00, 0x01]
+        .pread_with::<Data14>(0, LE)
+        .unwrap_err();
+    assert_eq!(err.path().unwrap().to_string(), "Data14::Prefixed.items[0]");
+}
+
+#[derive(Debug, PartialEq, Pread, Pwrite, SizeWith)]
+struct Flags20 {
+    enabled: bool,
+    #[scroll(ctx = scroll::ctx::BoolCtx::Lenient)]
+    visible: bool,
+    initial: char,
+    #[scroll(ctx = (ctx, scroll::ctx::Width::W32))]
+    len: usize,
+    marker: (),
+}
+
+#[test]
+fn test_bool_char_usize_fields() {
+    let bytes = [0x01, 0x02, 0x00, 0x00, 0x00, 0x41, 0x10, 0x00, 0x00, 0x00];
+    let flags: Flags20 = bytes.pread_with(0, scroll::BE).unwrap();
+    assert_eq!(
+        flags,
+        Flags20 {
+            enabled: true,
+            visible: true,
+            initial: 'A',
+            len: 0x1000_0000,
+            marker: (),
+        }
+    );
+    assert_eq!(Flags20::size_with(&scroll::BE), 10);
+    let mut out = [0u8; 10];
+    out.pwrite_with(&flags, 0, scroll::BE).unwrap();
+    assert_eq!(out[..2], [0x01, 0x01]);
+    assert_eq!(out[2..], bytes[2..]);
+
+    let err = [0x02u8; 10]
+        .pread_with::<Flags20>(0, scroll::BE)
+        .unwrap_err();
+    assert_eq!(err.path().unwrap().to_string(), "Flags20.enabled");
+}
+
+#[derive(Debug, PartialEq, IOread, IOwrite, SizeWith)]
+struct Flags20b {
+    enabled: bool,
+    initial: char,
+    marker: (),
+}
+
+#[test]
+fn test_bool_char_fields_ioread() {
+    let bytes = [0x01, 0x00, 0x00, 0x00, 0x41];
+    let flags: Flags20b = bytes.cread_with(0, scroll::BE);
+    assert_eq!(
+        flags,
+        Flags20b {
+            enabled: true,
+            initial: 'A',
+            marker: (),
+        }
+    );
+    let mut out = [0u8; 5];
+    out.cwrite_with(&flags, 0, scroll::BE);
+    assert_eq!(out, bytes);
+
+    // reading can't fail, so anything but 0 is `true` and invalid chars are replaced
+    let flags: Flags20b = [0x02, 0x00, 0x11, 0x00, 0x00].cread_with(0, scroll::BE);
+    assert!(flags.enabled);
+    assert_eq!(flags.initial, char::REPLACEMENT_CHARACTER);
+}
+
+#[derive(Debug, PartialEq, Pread, Pwrite, SizeWith)]
+struct Packet21 {
+    #[scroll(bits = 4)]
+    version: u8,
+    #[scroll(bits = 4)]
+    ihl: u8,
+    #[scroll(bits = 1)]
+    reserved: bool,
+    #[scroll(bits = 1)]
+    dont_fragment: bool,
+    #[scroll(bits = 1)]
+    more_fragments: bool,
+    #[scroll(bits = 13)]
+    fragment_offset: u16,
+    len: u16,
+}
+
+#[test]
+fn test_bit_fields() {
+    let bytes = [0x45, 0x40, 0x12, 0x00, 0x14];
+    let packet: Packet21 = bytes.pread_with(0, scroll::BE).unwrap();
+    assert_eq!(
+        packet,
+        Packet21 {
+            version: 4,
+            ihl: 5,
+            reserved: false,
+            dont_fragment: true,
+            more_fragments: false,
+            fragment_offset: 0x12,
+            len: 0x14,
+        }
+    );
+    assert_eq!(Packet21::size_with(&scroll::BE), 5);
+    let mut out = [0xffu8; 5];
+    assert_eq!(out.pwrite_with(&packet, 0, scroll::BE).unwrap(), 5);
+    assert_eq!(out, bytes);
+
+    let err = bytes[..2]
+        .pread_with::<Packet21>(0, scroll::BE)
+        .unwrap_err();
+    assert_eq!(err.path().unwrap().to_string(), "Packet21.fragment_offset");
+    let packet = Packet21 {
+        ihl: 0x10,
+        ..packet
+    };
+    assert!(out.pwrite_with(packet, 0, scroll::BE).is_err());
+}
+
+#[derive(Debug, PartialEq, Pread, Pwrite, MeasureWith)]
+struct Point22 {
+    x: u16,
+    y: u16,
+}
+
+#[derive(Debug, PartialEq, Pread, Pwrite, MeasureWith)]
+struct Entry22<'b> {
+    kind: u8,
+    #[scroll(ctx = scroll::ctx::StrCtx::Delimiter(0))]
+    name: &'b str,
+    #[scroll(ctx = ())]
+    id: scroll::Uleb128,
+    #[scroll(len_prefix = u8)]
+    points: Vec<Point22>,
+    flags: [u8; 2],
+    origin: Point22,
+}
+
+#[test]
+fn test_measure_with() {
+    use scroll::ctx::MeasureWith;
+
+    let bytes = [
+        0x07, b'a', b'b', 0x00, 0xe5, 0x8e, 0x26, 0x02, 0x01, 0x00, 0x02, 0x00, 0x03, 0x00, 0x04,
+        0x00, 0xaa, 0xbb, 0x05, 0x00, 0x06, 0x00,
+    ];
+    let entry: Entry22 = bytes.pread_with(0, LE).unwrap();
+    assert_eq!(entry.name, "ab");
+    assert_eq!(entry.points.len(), 2);
+    assert_eq!(entry.measure_with(&LE), bytes.len());
+    let mut out = vec![0u8; entry.measure_with(&LE)];
+    assert_eq!(out.pwrite_with(&entry, 0, LE).unwrap(), bytes.len());
+    assert_eq!(out, bytes);
+
+    let data = Data14::Prefixed {
+        items: vec![1, 2, 3],
+    };
+    assert_eq!(data.measure_with(&LE), 9);
+    assert_eq!(Data14::List(2, vec![0xaa, 0xbb]).measure_with(&LE), 4);
+}
+
+#[derive(Debug, PartialEq, Pread, Pwrite, MeasureWith)]
+struct Glyphs22 {
+    width: u8,
+    glyph: char,
+    #[scroll(ctx = scroll::ctx::CharCtx::Utf8)]
+    short: char,
+    #[scroll(ctx = (ctx, scroll::ctx::Width::W16))]
+    index: usize,
+    row: [char; 2],
+}
+
+#[test]
+fn test_measure_chars() {
+    use scroll::ctx::MeasureWith;
+
+    let glyphs = Glyphs22 {
+        width: 1,
+        glyph: 'x',
+        short: 'é',
+        index: 7,
+        row: ['a', 'b'],
+    };
+    assert_eq!(glyphs.measure_with(&LE), 1 + 4 + 2 + 2 + 8);
+    let mut out = vec![0u8; glyphs.measure_with(&LE)];
+    assert_eq!(out.pwrite_with(&glyphs, 0, LE).unwrap(), out.len());
+    assert_eq!(out.pread_with::<Glyphs22>(0, LE).unwrap(), glyphs);
+}
+
+#[derive(Debug, PartialEq, Clone, Copy, Pread, Pwrite, IOread, IOwrite, SizeWith)]
+struct Inner23 {
+    tag: u8,
+    value: u32,
+}
+
+#[derive(Debug, PartialEq, Pread, Pwrite, IOread, IOwrite, SizeWith)]
+struct Outer23 {
+    first: Inner23,
+    len: u16,
+    second: Inner23,
+    words: [u16; 2],
+}
+
+#[test]
+fn test_nested_padded_structs() {
+    // in memory each Inner23 is padded to 8 bytes, but it is 5 bytes on the wire
+    assert_eq!(std::mem::size_of::<Inner23>(), 8);
+    assert_eq!(Outer23::size_with(&LE), 16);
+
+    let bytes = [
+        0x01, 0x11, 0x22, 0x33, 0x44, 0x02, 0x00, 0x03, 0x55, 0x66, 0x77, 0x88, 0x04, 0x00, 0x05,
+        0x00,
+    ];
+    let outer: Outer23 = bytes.pread_with(0, LE).unwrap();
+    assert_eq!(
+        outer,
+        Outer23 {
+            first: Inner23 {
+                tag: 1,
+                value: 0x4433_2211,
+            },
+            len: 2,
+            second: Inner23 {
+                tag: 3,
+                value: 0x8877_6655,
+            },
+            words: [4, 5],
+        }
+    );
+    assert_eq!(bytes.cread_with::<Outer23>(0, LE), outer);
+
+    let mut written = [0u8; 16];
+    assert_eq!(written.pwrite_with(&outer, 0, LE).unwrap(), 16);
+    assert_eq!(written, bytes);
+    let mut written = [0u8; 16];
+    written.cwrite_with(&outer, 0, LE);
+    assert_eq!(written, bytes);
+}
+
+#[derive(Debug, PartialEq, Pread, Pwrite, IOread, IOwrite, SizeWith)]
+#[scroll(repr_c)]
+struct Layout24 {
+    tag: u8,
+    value: u32,
+    small: u16,
+}
+
+#[repr(C)]
+struct ReprC24 {
+    _tag: u8,
+    _value: u32,
+    _small: u16,
+}
+
+/// Laid out the same on every target, although `u64` is only 4-byte aligned on some
+#[derive(Debug, PartialEq, Pread, Pwrite, SizeWith)]
+#[scroll(repr_c)]
+struct Wide24 {
+    tag: u8,
+    value: u64,
+    pair: [u16; 2],
+}
+
+#[derive(Debug, PartialEq, Pread, Pwrite, IOread, IOwrite, SizeWith)]
+#[scroll(align = 8)]
+struct Note24 {
+    kind: u8,
+    #[scroll(pad = 3)]
+    len: u32,
+    #[scroll(align = 8)]
+    addr: u64,
+    flags: u8,
+}
+
+#[derive(Debug, PartialEq, Pread, Pwrite, MeasureWith)]
+#[scroll(pad = 2)]
+struct Reserved24 {
+    #[scroll(len_prefix = u8)]
+    data: Vec<u8>,
+    #[scroll(align = 4)]
+    id: u16,
+}
+
+#[test]
+fn test_alignment_and_padding() {
+    assert_eq!(Layout24::size_with(&LE), std::mem::size_of::<ReprC24>());
+    assert_eq!(Wide24::size_with(&LE), 24);
+    let wide = Wide24 {
+        tag: 1,
+        value: 2,
+        pair: [3, 4],
+    };
+    let mut out = [0xffu8; 24];
+    assert_eq!(out.pwrite_with(&wide, 0, LE).unwrap(), 24);
+    assert_eq!(out[8], 2);
+    assert_eq!(out[16..20], [3, 0, 4, 0]);
+    assert_eq!(out.pread_with::<Wide24>(0, LE).unwrap(), wide);
+    let bytes = [
+        0x01, 0xff, 0xff, 0xff, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0xff, 0xff,
+    ];
+    let layout: Layout24 = bytes.pread_with(0, LE).unwrap();
+    assert_eq!(
+        layout,
+        Layout24 {
+            tag: 1,
+            value: 0x4433_2211,
+            small: 0x6655,
+        }
+    );
+    assert_eq!(bytes.cread_with::<Layout24>(0, LE), layout);
+    let offset = &mut 0;
+    bytes.gread_with::<Layout24>(offset, LE).unwrap();
+    assert_eq!(*offset, 12);
+    let mut out = [0xffu8; 12];
+    assert_eq!(out.pwrite_with(&layout, 0, LE).unwrap(), 12);
+    assert_eq!(
+        out[..10],
+        [0x01, 0, 0, 0, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66]
+    );
+    assert_eq!(out[10..], [0, 0]);
+    let mut cout = [0xffu8; 12];
+    cout.cwrite_with(&layout, 0, LE);
+    assert_eq!(cout, out);
+
+    let note = Note24 {
+        kind: 2,
+        len: 3,
+        addr: 0x1000,
+        flags: 1,
+    };
+    assert_eq!(Note24::size_with(&LE), 24);
+    let mut out = [0xffu8; 24];
+    assert_eq!(out.pwrite_with(&note, 0, LE).unwrap(), 24);
+    assert_eq!(out[..8], [2, 0, 0, 0, 3, 0, 0, 0]);
+    assert_eq!(out[8..16], 0x1000u64.to_le_bytes());
+    assert_eq!(out[16..], [1, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(out.pread_with::<Note24>(0, LE).unwrap(), note);
+    assert_eq!(out.cread_with::<Note24>(0, LE), note);
+    let mut cout = [0xffu8; 24];
+    cout.cwrite_with(&note, 0, LE);
+    assert_eq!(cout, out);
+    // the padding at the end must be there as well
+    let err = out[..20].pread_with::<Note24>(0, LE).unwrap_err();
+    assert_eq!(err.offset(), Some(17));
+    assert_eq!(err.to_string(), "type is too big (7) for 3 at offset 0x11");
+    assert!(out.pwrite_with(&note, 8, LE).is_err());
+
+    let reserved = Reserved24 {
+        data: vec![7, 8],
+        id: 0xabcd,
+    };
+    use scroll::ctx::MeasureWith;
+    assert_eq!(reserved.measure_with(&LE), 8);
+    let mut out = [0xffu8; 8];
+    assert_eq!(out.pwrite_with(&reserved, 0, LE).unwrap(), 8);
+    assert_eq!(out, [2, 7, 8, 0, 0xcd, 0xab, 0, 0]);
+    assert_eq!(out.pread_with::<Reserved24>(0, LE).unwrap(), reserved);
+}
+
+#[derive(Debug, PartialEq, Pread, Pwrite, IOwrite, SizeWith)]
+#[scroll(magic = b"\x7fELF")]
+struct Ident25 {
+    #[scroll(assert = "self.class == 1 || self.class == 2")]
+    class: u8,
+    #[scroll(const = 1)]
+    version: u8,
+    #[scroll(assert = self.entry % 4 == 0)]
+    entry: u32,
+}
+
+#[test]
+fn test_magic_and_constants() {
+    let bytes = [0x7f, b'E', b'L', b'F', 0x02, 0x01, 0x10, 0x00, 0x00, 0x00];
+    let ident: Ident25 = bytes.pread_with(0, LE).unwrap();
+    assert_eq!(
+        ident,
+        Ident25 {
+            class: 2,
+            version: 1,
+            entry: 0x10,
+        }
+    );
+    assert_eq!(Ident25::size_with(&LE), 10);
+
+    // the magic and the constant are written regardless of the field
+    let ident = Ident25 {
+        version: 0,
+        ..ident
+    };
+    let mut out = [0u8; 10];
+    assert_eq!(out.pwrite_with(&ident, 0, LE).unwrap(), 10);
+    assert_eq!(out, bytes);
+    let mut out = [0u8; 10];
+    out.cwrite_with(&ident, 0, LE);
+    assert_eq!(out, bytes);
+
+    let mut bad = bytes;
+    bad[1] = b'X';
+    let err = bad.pread_with::<Ident25>(0, LE).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "bad input bad magic number for Ident25 (4) at offset 0x0 in Ident25"
+    );
+    assert_eq!(err.path().unwrap().type_name(), Some("Ident25"));
+    assert!(bytes[..3].pread_with::<Ident25>(0, LE).is_err());
+
+    let mut bad = bytes;
+    bad[5] = 2;
+    let err = bad.pread_with::<Ident25>(0, LE).unwrap_err();
+    assert_eq!(err.offset(), Some(5));
+    assert_eq!(err.path().unwrap().to_string(), "Ident25.version");
+    assert!(err.to_string().contains("expected the constant 1"));
+
+    let mut bad = bytes;
+    bad[4] = 3;
+    let err = bad.pread_with::<Ident25>(0, LE).unwrap_err();
+    assert_eq!(err.path().unwrap().to_string(), "Ident25.class");
+    assert!(err.to_string().contains("assertion failed"));
+
+    let mut bad = bytes;
+    bad[6] = 0x11;
+    let err = bad.pread_with::<Ident25>(0, LE).unwrap_err();
+    assert!(matches!(err, scroll::Error::At { .. }));
+    assert_eq!(err.offset(), Some(6));
+    assert_eq!(err.path().unwrap().to_string(), "Ident25.entry");
+}
+
+#[test]
+fn test_magic_and_constants_streamed() {
+    use scroll::IOread;
+    use std::io::Cursor;
+
+    let bytes = [0x7f, b'E', b'L', b'F', 0x02, 0x01, 0x10, 0x00, 0x00, 0x00];
+    let ident: Ident25 = Cursor::new(&bytes[..]).ioread_try_with(LE).unwrap();
+    assert_eq!(ident.entry, 0x10);
+
+    let mut bad = bytes;
+    bad[2] = b'X';
+    let err = Cursor::new(&bad[..])
+        .ioread_try_with::<Ident25>(LE)
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "bad input bad magic number for Ident25 (4) at offset 0x0 in Ident25"
+    );
+
+    let mut bad = bytes;
+    bad[5] = 2;
+    let err = Cursor::new(&bad[..])
+        .ioread_try_with::<Ident25>(LE)
+        .unwrap_err();
+    assert_eq!(err.path().unwrap().to_string(), "Ident25.version");
+}
@@ -0,0 +1,228 @@
+use core::future::Future;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use std::io::{ErrorKind, Result};
+
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+use crate::ctx::{FromCtx, IntoCtx, SizeWith};
+use crate::lesser::Scratch;
+
+/// An extension trait to `tokio::io::AsyncRead` streams; the asynchronous counterpart of
+/// [`IOread`](trait.IOread.html), reading types implementing [`FromCtx`](ctx/trait.FromCtx.html)
+/// and [`SizeWith`](ctx/trait.SizeWith.html).
+///
+/// Requires the `tokio` feature.
+///
+/// # Example
+/// ```rust
+/// use scroll::{AsyncIOread, LE};
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let bytes = [0xef, 0xbe, 0xad, 0xde, 0x2a];
+/// let mut stream = &bytes[..];
+/// let deadbeef = stream.ioread_with::<u32>(LE).await.unwrap();
+/// assert_eq!(deadbeef, 0xdeadbeef);
+/// let answer = stream.ioread_with::<u8>(LE).await.unwrap();
+/// assert_eq!(answer, 42);
+/// assert!(stream.ioread_with::<u8>(LE).await.is_err());
+/// # });
+/// ```
+pub trait AsyncIOread<Ctx: Copy>: AsyncRead + Unpin {
+    /// Reads the type `N` from `Self`, with a default parsing context.
+    /// For the primitive numeric types, this will be at the host machine's endianness.
+    #[inline]
+    fn ioread<N: FromCtx<Ctx> + SizeWith<Ctx>>(&mut self) -> IOreadFuture<'_, Self, N, Ctx>
+    where
+        Ctx: Default,
+    {
+        self.ioread_with(Ctx::default())
+    }
+
+    /// Reads the type `N` from `Self`, with the parsing context `ctx`.
+    /// The future fails with an `UnexpectedEof` error if the stream ends before all of `N` is read.
+    #[inline]
+    fn ioread_with<N: FromCtx<Ctx> + SizeWith<Ctx>>(
+        &mut self,
+        ctx: Ctx,
+    ) -> IOreadFuture<'_, Self, N, Ctx> {
+        IOreadFuture {
+            reader: self,
+            ctx,
+            scratch: None,
+            filled: 0,
+            n: PhantomData,
+        }
+    }
+}
+
+/// Types that implement `AsyncRead` get methods defined in `AsyncIOread` for free.
+impl<Ctx: Copy, R: AsyncRead + Unpin + ?Sized> AsyncIOread<Ctx> for R {}
+
+/// An extension trait to `tokio::io::AsyncWrite` streams; the asynchronous counterpart of
+/// [`IOwrite`](trait.IOwrite.html), writing types implementing [`IntoCtx`](ctx/trait.IntoCtx.html)
+/// and [`SizeWith`](ctx/trait.SizeWith.html).
+///
+/// Requires the `tokio` feature.
+///
+/// # Example
+/// ```rust
+/// use scroll::{AsyncIOwrite, BE};
+///
+/// # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+/// let mut stream = Vec::new();
+/// stream.iowrite_with(0xdeadbeefu32, BE).await.unwrap();
+/// stream.iowrite_with(42u8, BE).await.unwrap();
+/// assert_eq!(stream, [0xde, 0xad, 0xbe, 0xef, 0x2a]);
+/// # });
+/// ```
+pub trait AsyncIOwrite<Ctx: Copy>: AsyncWrite + Unpin {
+    /// Writes the type `N` into `Self`, with a default parsing context.
+    /// For the primitive numeric types, this will be at the host machine's endianness.
+    #[inline]
+    fn iowrite<N: SizeWith<Ctx> + IntoCtx<Ctx>>(&mut self, n: N) -> IOwriteFuture<'_, Self, N, Ctx>
+    where
+        Ctx: Default,
+    {
+        self.iowrite_with(n, Ctx::default())
+    }
+
+    /// Writes the type `N` into `Self`, with the parsing context `ctx`.
+    /// The future fails with a `WriteZero` error if the stream stops accepting bytes before all of
+    /// `N` is written.
+    #[inline]
+    fn iowrite_with<N: SizeWith<Ctx> + IntoCtx<Ctx>>(
+        &mut self,
+        n: N,
+        ctx: Ctx,
+    ) -> IOwriteFuture<'_, Self, N, Ctx> {
+        IOwriteFuture {
+            writer: self,
+            ctx,
+            n: Some(n),
+            scratch: None,
+            written: 0,
+        }
+    }
+}
+
+/// Types that implement `AsyncWrite` get methods defined in `AsyncIOwrite` for free.
+impl<Ctx: Copy, W: AsyncWrite + Unpin + ?Sized> AsyncIOwrite<Ctx> for W {}
+
+/// The future returned by [`AsyncIOread::ioread_with`](trait.AsyncIOread.html#method.ioread_with)
+#[must_use = "futures do nothing unless polled"]
+pub struct IOreadFuture<'a, R: ?Sized, N, Ctx> {
+    reader: &'a mut R,
+    ctx: Ctx,
+    scratch: Option<Scratch>,
+    filled: usize,
+    n: PhantomData<fn() -> N>,
+}
+
+// nothing is ever pinned through the future
+impl<'a, R: ?Sized, N, Ctx> Unpin for IOreadFuture<'a, R, N, Ctx> {}
+
+impl<'a, R, N, Ctx> Future for IOreadFuture<'a, R, N, Ctx>
+where
+    R: AsyncRead + Unpin + ?Sized,
+    N: FromCtx<Ctx> + SizeWith<Ctx>,
+    Ctx: Copy,
+{
+    type Output = Result<N>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let scratch = match this.scratch {
+            Some(ref mut scratch) => scratch,
+            None => this.scratch.insert(Scratch::new(N::size_with(&this.ctx))?),
+        };
+        let buf = scratch.as_mut();
+        while this.filled < buf.len() {
+            let mut unfilled = ReadBuf::new(&mut buf[this.filled..]);
+            match Pin::new(&mut *this.reader).poll_read(cx, &mut unfilled) {
+                Poll::Ready(Ok(())) => {}
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            }
+            match unfilled.filled().len() {
+                0 => return Poll::Ready(Err(ErrorKind::UnexpectedEof.into())),
+                read => this.filled += read,
+            }
+        }
+        Poll::Ready(Ok(N::from_ctx(buf, this.ctx)))
+    }
+}
+
+/// The future returned by [`AsyncIOwrite::iowrite_with`](trait.AsyncIOwrite.html#method.iowrite_with)
+#[must_use = "futures do nothing unless polled"]
+pub struct IOwriteFuture<'a, W: ?Sized, N, Ctx> {
+    writer: &'a mut W,
+    ctx: Ctx,
+    /// Taken once it is serialized into `scratch`
+    n: Option<N>,
+    scratch: Option<Scratch>,
+    written: usize,
+}
+
+// nothing is ever pinned through the future
+impl<'a, W: ?Sized, N, Ctx> Unpin for IOwriteFuture<'a, W, N, Ctx> {}
+
+impl<'a, W, N, Ctx> Future for IOwriteFuture<'a, W, N, Ctx>
+where
+    W: AsyncWrite + Unpin + ?Sized,
+    N: SizeWith<Ctx> + IntoCtx<Ctx>,
+    Ctx: Copy,
+{
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let scratch = match this.scratch {
+            Some(ref mut scratch) => scratch,
+            None => {
+                let mut scratch = Scratch::new(N::size_with(&this.ctx))?;
+                if let Some(n) = this.n.take() {
+                    n.into_ctx(scratch.as_mut(), this.ctx);
+                }
+                this.scratch.insert(scratch)
+            }
+        };
+        let buf = scratch.as_mut();
+        while this.written < buf.len() {
+            match Pin::new(&mut *this.writer).poll_write(cx, &buf[this.written..]) {
+                Poll::Ready(Ok(0)) => return Poll::Ready(Err(ErrorKind::WriteZero.into())),
+                Poll::Ready(Ok(written)) => this.written += written,
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AsyncIOread, AsyncIOwrite};
+    use crate::{BE, LE};
+
+    #[tokio::test]
+    async fn duplex_round_trip() {
+        // a small buffer makes values straddle several reads and writes
+        let (mut client, mut server) = tokio::io::duplex(7);
+        let writer = tokio::spawn(async move {
+            client.iowrite_with(0xdeadbeefu32, BE).await.unwrap();
+            client.iowrite_with(-2i16, LE).await.unwrap();
+            client.iowrite_with(1.5f64, LE).await.unwrap();
+            client.iowrite(7u8).await.unwrap();
+        });
+        assert_eq!(server.ioread_with::<u32>(BE).await.unwrap(), 0xdeadbeef);
+        assert_eq!(server.ioread_with::<i16>(LE).await.unwrap(), -2);
+        assert_eq!(server.ioread_with::<f64>(LE).await.unwrap(), 1.5);
+        assert_eq!(server.ioread::<u8>().await.unwrap(), 7);
+        writer.await.unwrap();
+        // the client is dropped, so the stream ends
+        let err = server.ioread_with::<u16>(LE).await.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+}
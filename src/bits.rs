@@ -0,0 +1,316 @@
+use crate::error;
+
+/// The order bits are taken from each byte by a [`BitReader`](struct.BitReader.html), and put
+/// into each byte by a [`BitWriter`](struct.BitWriter.html).
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BitOrder {
+    /// Starting at the most significant bit of each byte, with the first bit read being the most
+    /// significant bit of the value, as in network headers
+    MsbFirst,
+    /// Starting at the least significant bit of each byte, with the first bit read being the least
+    /// significant bit of the value, as in DEFLATE streams
+    LsbFirst,
+}
+
+impl Default for BitOrder {
+    #[inline]
+    fn default() -> Self {
+        BitOrder::MsbFirst
+    }
+}
+
+/// The types bit fields can be read into and written from
+pub trait BitField: Sized {
+    /// How many bits a value holds
+    const BITS: u32;
+    /// The value of the low `Self::BITS` bits of `bits`
+    fn from_bits(bits: u64) -> Self;
+    /// The bits of the value
+    fn to_bits(self) -> u64;
+}
+
+macro_rules! bit_field_impl {
+    ($typ:ty) => {
+        impl BitField for $typ {
+            const BITS: u32 = <$typ>::BITS;
+            #[inline]
+            fn from_bits(bits: u64) -> Self {
+                bits as $typ
+            }
+            #[inline]
+            fn to_bits(self) -> u64 {
+                self as u64
+            }
+        }
+    };
+}
+
+bit_field_impl!(u8);
+bit_field_impl!(u16);
+bit_field_impl!(u32);
+bit_field_impl!(u64);
+
+impl BitField for bool {
+    const BITS: u32 = 1;
+    #[inline]
+    fn from_bits(bits: u64) -> Self {
+        bits & 1 != 0
+    }
+    #[inline]
+    fn to_bits(self) -> u64 {
+        self as u64
+    }
+}
+
+/// A mask of the low `count` bits, for `count` up to 64
+#[inline]
+fn mask(count: u32) -> u64 {
+    u64::MAX.checked_shr(64 - count).unwrap_or(0)
+}
+
+/// Checks that `count` bits fit into a `N`, and that they are within the `len` bytes of the
+/// buffer at `bit`
+fn check<N: BitField>(count: u32, bit: usize, len: usize) -> error::Result<()> {
+    let size = (bit % 8 + count as usize + 7) / 8;
+    if count > N::BITS {
+        return Err(error::Error::BadInput {
+            size,
+            msg: "more bits than the type holds",
+        });
+    }
+    let available = len.saturating_sub(bit / 8);
+    if size > available {
+        return Err(error::Error::TooBig {
+            size,
+            len: available,
+        });
+    }
+    Ok(())
+}
+
+/// A cursor reading values bit by bit from a byte slice, for headers packing fields of less than
+/// a byte or straddling bytes.
+///
+/// The bit offset starts at the start of the slice; after the bit fields,
+/// [`align`](#method.align) skips to the next whole byte and returns its offset, to continue
+/// with [Pread](trait.Pread.html).
+///
+/// # Example
+/// ```rust
+/// use scroll::{BitOrder, BitReader};
+/// // an IPv4 header starts with a 4 bit version and a 4 bit header length
+/// let bytes = [0x45, 0x00, 0xab, 0xcd];
+/// let mut bits = BitReader::new(&bytes, BitOrder::MsbFirst);
+/// assert_eq!(bits.read_bits::<u8>(4).unwrap(), 4);
+/// assert_eq!(bits.read_bits::<u8>(4).unwrap(), 5);
+/// assert!(!bits.read_bits::<bool>(1).unwrap());
+/// assert_eq!(bits.align(), 2);
+/// assert_eq!(bits.read_bits::<u16>(12).unwrap(), 0xabc);
+/// assert!(bits.read_bits::<u8>(8).is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct BitReader<'a> {
+    bytes: &'a [u8],
+    bit: usize,
+    order: BitOrder,
+}
+
+impl<'a> BitReader<'a> {
+    /// Create a reader at the start of `bytes`
+    #[inline]
+    pub fn new(bytes: &'a [u8], order: BitOrder) -> Self {
+        BitReader {
+            bytes,
+            bit: 0,
+            order,
+        }
+    }
+
+    /// The offset of the reader from the start of its bytes, in bits
+    #[inline]
+    pub fn bit_offset(&self) -> usize {
+        self.bit
+    }
+
+    /// Whether the reader is at the start of a byte
+    #[inline]
+    pub fn is_aligned(&self) -> bool {
+        self.bit % 8 == 0
+    }
+
+    /// Skip the rest of the current byte, unless the reader is at the start of one, and return the
+    /// offset of the reader in bytes
+    #[inline]
+    pub fn align(&mut self) -> usize {
+        self.bit = (self.bit + 7) / 8 * 8;
+        self.bit / 8
+    }
+
+    /// Reads the next `count` bits into a `N`; fails with `BadInput` if a `N` holds fewer bits,
+    /// and with `TooBig` if the bytes end before, without advancing in either case
+    pub fn read_bits<N: BitField>(&mut self, count: u32) -> error::Result<N> {
+        check::<N>(count, self.bit, self.bytes.len())?;
+        let mut value = 0u64;
+        let mut read = 0;
+        while read < count {
+            let byte = self.bytes[self.bit / 8] as u64;
+            let used = (self.bit % 8) as u32;
+            let take = (8 - used).min(count - read);
+            match self.order {
+                BitOrder::MsbFirst => {
+                    let chunk = (byte >> (8 - used - take)) & mask(take);
+                    value = value.checked_shl(take).unwrap_or(0) | chunk;
+                }
+                BitOrder::LsbFirst => {
+                    let chunk = (byte >> used) & mask(take);
+                    value |= chunk << read;
+                }
+            }
+            read += take;
+            self.bit += take as usize;
+        }
+        Ok(N::from_bits(value))
+    }
+}
+
+/// A cursor writing values bit by bit into a byte slice; the writing counterpart of
+/// [BitReader](struct.BitReader.html).
+///
+/// Only the bits written to are changed, so the rest of a partially written byte keeps its value.
+///
+/// # Example
+/// ```rust
+/// use scroll::{BitOrder, BitWriter};
+/// let mut bytes = [0u8; 3];
+/// let mut bits = BitWriter::new(&mut bytes, BitOrder::MsbFirst);
+/// bits.write_bits(4u8, 4).unwrap();
+/// bits.write_bits(5u8, 4).unwrap();
+/// bits.write_bits(true, 1).unwrap();
+/// assert_eq!(bits.align(), 2);
+/// assert!(bits.write_bits(0x100u16, 8).is_err());
+/// bits.write_bits(0xabu8, 8).unwrap();
+/// assert_eq!(bytes, [0x45, 0x80, 0xab]);
+/// ```
+#[derive(Debug)]
+pub struct BitWriter<'a> {
+    bytes: &'a mut [u8],
+    bit: usize,
+    order: BitOrder,
+}
+
+impl<'a> BitWriter<'a> {
+    /// Create a writer at the start of `bytes`
+    #[inline]
+    pub fn new(bytes: &'a mut [u8], order: BitOrder) -> Self {
+        BitWriter {
+            bytes,
+            bit: 0,
+            order,
+        }
+    }
+
+    /// The offset of the writer from the start of its bytes, in bits
+    #[inline]
+    pub fn bit_offset(&self) -> usize {
+        self.bit
+    }
+
+    /// Whether the writer is at the start of a byte
+    #[inline]
+    pub fn is_aligned(&self) -> bool {
+        self.bit % 8 == 0
+    }
+
+    /// Skip the rest of the current byte, unless the writer is at the start of one, and return the
+    /// offset of the writer in bytes
+    #[inline]
+    pub fn align(&mut self) -> usize {
+        self.bit = (self.bit + 7) / 8 * 8;
+        self.bit / 8
+    }
+
+    /// Writes `value` as the next `count` bits; fails with `BadInput` if it does not fit into them,
+    /// and with `TooBig` if the bytes end before, without writing anything in either case
+    pub fn write_bits<N: BitField>(&mut self, value: N, count: u32) -> error::Result<()> {
+        check::<N>(count, self.bit, self.bytes.len())?;
+        let value = value.to_bits();
+        if value & !mask(count) != 0 {
+            return Err(error::Error::BadInput {
+                size: (self.bit % 8 + count as usize + 7) / 8,
+                msg: "value does not fit into its bits",
+            });
+        }
+        let mut written = 0;
+        while written < count {
+            let byte = &mut self.bytes[self.bit / 8];
+            let used = (self.bit % 8) as u32;
+            let take = (8 - used).min(count - written);
+            let (chunk, shift) = match self.order {
+                BitOrder::MsbFirst => (value >> (count - written - take), 8 - used - take),
+                BitOrder::LsbFirst => (value >> written, used),
+            };
+            let chunk_mask = (mask(take) << shift) as u8;
+            *byte = (*byte & !chunk_mask) | (((chunk & mask(take)) << shift) as u8);
+            written += take;
+            self.bit += take as usize;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BitOrder, BitReader, BitWriter};
+    use crate::Error;
+
+    #[test]
+    fn bits_round_trip() {
+        for order in [BitOrder::MsbFirst, BitOrder::LsbFirst] {
+            let mut bytes = [0xffu8; 7];
+            let mut writer = BitWriter::new(&mut bytes, order);
+            writer.write_bits(0x5u8, 3).unwrap();
+            writer.write_bits(0x1234u16, 13).unwrap();
+            writer.write_bits(false, 1).unwrap();
+            writer.write_bits(0xdead_beefu64, 32).unwrap();
+            assert_eq!(writer.bit_offset(), 49);
+            assert!(matches!(
+                writer.write_bits(0u8, 8),
+                Err(Error::TooBig { size: 2, len: 1 })
+            ));
+            assert!(!writer.is_aligned());
+            assert_eq!(writer.align(), 7);
+            // the last bit written is set, and the ones after it are untouched
+            assert_eq!(bytes[6], 0xff);
+
+            let mut reader = BitReader::new(&bytes, order);
+            assert_eq!(reader.read_bits::<u8>(3).unwrap(), 0x5);
+            assert_eq!(reader.read_bits::<u16>(13).unwrap(), 0x1234);
+            assert!(!reader.read_bits::<bool>(1).unwrap());
+            assert_eq!(reader.read_bits::<u64>(32).unwrap(), 0xdead_beef);
+            assert_eq!(reader.read_bits::<u8>(0).unwrap(), 0);
+            assert!(matches!(
+                reader.read_bits::<u8>(9),
+                Err(Error::BadInput { .. })
+            ));
+            assert_eq!(reader.bit_offset(), 49);
+        }
+    }
+
+    #[test]
+    fn bit_orders() {
+        let bytes = [0b1100_0101, 0b0000_0011];
+        let mut msb = BitReader::new(&bytes, BitOrder::MsbFirst);
+        assert_eq!(msb.read_bits::<u8>(2).unwrap(), 0b11);
+        assert_eq!(msb.read_bits::<u16>(10).unwrap(), 0b00_0101_0000);
+        let mut lsb = BitReader::new(&bytes, BitOrder::LsbFirst);
+        assert_eq!(lsb.read_bits::<u8>(2).unwrap(), 0b01);
+        assert_eq!(lsb.read_bits::<u16>(10).unwrap(), 0b00_1111_0001);
+
+        let mut out = [0u8; 2];
+        let mut writer = BitWriter::new(&mut out, BitOrder::LsbFirst);
+        writer.write_bits(0b01u8, 2).unwrap();
+        writer.write_bits(0b00_1111_0001u16, 10).unwrap();
+        assert!(writer.write_bits(0b100u8, 2).is_err());
+        assert_eq!(out, bytes);
+    }
+}
@@ -0,0 +1,455 @@
+use core::result;
+use std::collections::VecDeque;
+
+use crate::ctx::{TryFromCtx, TryIntoCtx};
+use crate::error;
+
+/// A byte source made of several contiguous chunks, like a [`Chunks`] rope or a `VecDeque<u8>`
+/// ring buffer, addressed as if it were a single slice.
+///
+/// Values are read from and written to such sources with [`ChunkedPread`] and [`ChunkedPwrite`].
+pub trait Chunked {
+    /// The number of bytes in all chunks together
+    fn len(&self) -> usize;
+
+    /// Whether there are no bytes in any chunk
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The bytes from `offset` to the end of the chunk it is in; empty if `offset` is out of bounds
+    fn chunk_at(&self, offset: usize) -> &[u8];
+}
+
+/// A [`Chunked`] byte source whose chunks can be written to
+pub trait ChunkedMut: Chunked {
+    /// The bytes from `offset` to the end of the chunk it is in; empty if `offset` is out of bounds
+    fn chunk_at_mut(&mut self, offset: usize) -> &mut [u8];
+}
+
+/// Appends the bytes from `start` to `end` of `src` to `dst`
+fn gather<S: Chunked + ?Sized>(src: &S, start: usize, end: usize, dst: &mut Vec<u8>) {
+    while start + dst.len() < end {
+        let chunk = src.chunk_at(start + dst.len());
+        let take = chunk.len().min(end - start - dst.len());
+        dst.extend_from_slice(&chunk[..take]);
+    }
+}
+
+/// Copies `src` into `dst` from `start` on
+fn scatter<D: ChunkedMut + ?Sized>(dst: &mut D, start: usize, mut src: &[u8]) {
+    let mut offset = start;
+    while !src.is_empty() {
+        let chunk = dst.chunk_at_mut(offset);
+        let take = chunk.len().min(src.len());
+        chunk[..take].copy_from_slice(&src[..take]);
+        src = &src[take..];
+        offset += take;
+    }
+}
+
+/// Reading values from [`Chunked`] byte sources; the counterpart of [Pread](trait.Pread.html) for
+/// buffers which are not contiguous.
+///
+/// Values are parsed right out of the chunk they start in whenever they fit into it. Values
+/// straddling chunk boundaries are copied into a scratch buffer first, as far as their parsing asks
+/// for more bytes by failing with `TooBig` or `BadOffset` errors. Since values can not borrow from
+/// that scratch buffer, only types owning their data can be read, i.e. not `&str` or `&[u8]`.
+///
+/// # Example
+/// ```rust
+/// use scroll::{ChunkedPread, Chunks, Uleb128, BE};
+/// let chunks: Chunks<&[u8]> = vec![&[0xde, 0xad, 0xbe][..], &[0xef, 0xe5, 0x8e][..], &[0x26][..]]
+///     .into_iter()
+///     .collect();
+/// let offset = &mut 0;
+/// assert_eq!(chunks.gread_with::<u32>(offset, BE).unwrap(), 0xdeadbeef);
+/// assert_eq!(u64::from(chunks.gread_with::<Uleb128>(offset, ()).unwrap()), 624485);
+/// assert_eq!(*offset, 7);
+/// assert!(chunks.gread_with::<u8>(offset, BE).is_err());
+/// ```
+pub trait ChunkedPread<Ctx: Copy, E>: Chunked {
+    /// Reads a value from `self` at `offset` with a default `Ctx`
+    #[inline]
+    fn pread<N: for<'a> TryFromCtx<'a, Ctx, Error = E>>(
+        &self,
+        offset: usize,
+    ) -> result::Result<N, E>
+    where
+        Ctx: Default,
+    {
+        self.pread_with(offset, Ctx::default())
+    }
+
+    /// Reads a value from `self` at `offset` with the given `ctx`
+    #[inline]
+    fn pread_with<N: for<'a> TryFromCtx<'a, Ctx, Error = E>>(
+        &self,
+        offset: usize,
+        ctx: Ctx,
+    ) -> result::Result<N, E> {
+        let mut ignored = offset;
+        self.gread_with(&mut ignored, ctx)
+    }
+
+    /// Reads a value from `self` at `offset` with a default `Ctx`, and updates the offset
+    #[inline]
+    fn gread<N: for<'a> TryFromCtx<'a, Ctx, Error = E>>(
+        &self,
+        offset: &mut usize,
+    ) -> result::Result<N, E>
+    where
+        Ctx: Default,
+    {
+        self.gread_with(offset, Ctx::default())
+    }
+
+    /// Reads a value from `self` at `offset` with the given `ctx`, and updates the offset
+    fn gread_with<N: for<'a> TryFromCtx<'a, Ctx, Error = E>>(
+        &self,
+        offset: &mut usize,
+        ctx: Ctx,
+    ) -> result::Result<N, E>;
+}
+
+impl<Ctx: Copy, E: From<error::Error> + error::Locate, S: Chunked + ?Sized> ChunkedPread<Ctx, E>
+    for S
+{
+    fn gread_with<N: for<'a> TryFromCtx<'a, Ctx, Error = E>>(
+        &self,
+        offset: &mut usize,
+        ctx: Ctx,
+    ) -> result::Result<N, E> {
+        let start = *offset;
+        if start >= self.len() {
+            return Err(error::Error::BadOffset(0).at(start).into());
+        }
+        let chunk = self.chunk_at(start);
+        let mut available = chunk.len();
+        let mut result = N::try_from_ctx(chunk, ctx);
+        let mut scratch = Vec::new();
+        loop {
+            let needed = match result {
+                Ok((_, size)) if size <= available => break,
+                Ok((_, size)) => size,
+                Err(ref err) => match err.needed_len(available) {
+                    Some(needed) => needed,
+                    None => break,
+                },
+            };
+            let end = start.saturating_add(needed).min(self.len());
+            if end - start <= available {
+                break;
+            }
+            gather(self, start, end, &mut scratch);
+            available = scratch.len();
+            result = N::try_from_ctx(&scratch, ctx);
+        }
+        result
+            .map(|(n, size)| {
+                *offset += size;
+                n
+            })
+            .map_err(|err| err.at(start))
+    }
+}
+
+/// Writing values to [`ChunkedMut`] byte sources; the counterpart of [Pwrite](trait.Pwrite.html)
+/// for buffers which are not contiguous.
+///
+/// Values are written right into the chunk they start in whenever they fit into it. Otherwise they
+/// are written into a scratch buffer holding the bytes they straddle, as far as writing asks for
+/// more bytes by failing with `TooBig` or `BadOffset` errors, which is then copied back.
+///
+/// # Example
+/// ```rust
+/// use std::collections::VecDeque;
+/// use scroll::{ChunkedPread, ChunkedPwrite, LE};
+/// let mut ring: VecDeque<u8> = VecDeque::with_capacity(4);
+/// ring.extend([0, 0, 0, 0]);
+/// ring.pop_front();
+/// ring.push_back(0);
+/// let (front, back) = ring.as_slices();
+/// assert_eq!((front.len(), back.len()), (3, 1));
+/// ring.pwrite_with(0xdeadbeefu32, 0, LE).unwrap();
+/// assert_eq!(ring.pread_with::<u32>(0, LE).unwrap(), 0xdeadbeef);
+/// ```
+pub trait ChunkedPwrite<Ctx: Copy, E>: ChunkedMut {
+    /// Writes `n` into `self` at `offset` with a default `Ctx`
+    #[inline]
+    fn pwrite<N: TryIntoCtx<Ctx, Error = E> + Clone>(
+        &mut self,
+        n: N,
+        offset: usize,
+    ) -> result::Result<usize, E>
+    where
+        Ctx: Default,
+    {
+        self.pwrite_with(n, offset, Ctx::default())
+    }
+
+    /// Writes `n` into `self` at `offset` with the given `ctx`
+    #[inline]
+    fn pwrite_with<N: TryIntoCtx<Ctx, Error = E> + Clone>(
+        &mut self,
+        n: N,
+        offset: usize,
+        ctx: Ctx,
+    ) -> result::Result<usize, E> {
+        let mut ignored = offset;
+        self.gwrite_with(n, &mut ignored, ctx)
+    }
+
+    /// Writes `n` into `self` at `offset` with a default `Ctx`, and updates the offset
+    #[inline]
+    fn gwrite<N: TryIntoCtx<Ctx, Error = E> + Clone>(
+        &mut self,
+        n: N,
+        offset: &mut usize,
+    ) -> result::Result<usize, E>
+    where
+        Ctx: Default,
+    {
+        self.gwrite_with(n, offset, Ctx::default())
+    }
+
+    /// Writes `n` into `self` at `offset` with the given `ctx`, and updates the offset
+    fn gwrite_with<N: TryIntoCtx<Ctx, Error = E> + Clone>(
+        &mut self,
+        n: N,
+        offset: &mut usize,
+        ctx: Ctx,
+    ) -> result::Result<usize, E>;
+}
+
+impl<Ctx: Copy, E: From<error::Error> + error::Locate, S: ChunkedMut + ?Sized> ChunkedPwrite<Ctx, E>
+    for S
+{
+    fn gwrite_with<N: TryIntoCtx<Ctx, Error = E> + Clone>(
+        &mut self,
+        n: N,
+        offset: &mut usize,
+        ctx: Ctx,
+    ) -> result::Result<usize, E> {
+        let start = *offset;
+        if start >= self.len() {
+            return Err(error::Error::BadOffset(0).at(start).into());
+        }
+        let chunk = self.chunk_at_mut(start);
+        let mut available = chunk.len();
+        let mut result = n.clone().try_into_ctx(chunk, ctx);
+        let mut scratch = Vec::new();
+        loop {
+            let needed = match result {
+                Ok(_) => break,
+                Err(ref err) => match err.needed_len(available) {
+                    Some(needed) => needed,
+                    None => break,
+                },
+            };
+            let end = start.saturating_add(needed).min(self.len());
+            if end - start <= available {
+                break;
+            }
+            // bytes the value skips over stay as they are
+            gather(self, start, end, &mut scratch);
+            available = scratch.len();
+            result = n.clone().try_into_ctx(&mut scratch, ctx);
+            if result.is_ok() {
+                scatter(self, start, &scratch);
+            }
+        }
+        result
+            .map(|size| {
+                *offset += size;
+                size
+            })
+            .map_err(|err| err.at(start))
+    }
+}
+
+/// A rope of byte chunks, e.g. the fragments of a reassembled packet, which values can be read
+/// from with [`ChunkedPread`] and written to with [`ChunkedPwrite`] as if it were one contiguous
+/// buffer.
+///
+/// Any chunk type giving access to its bytes can be used, like `&[u8]` or `Vec<u8>`; looking up
+/// the chunk an offset is in takes logarithmic time.
+///
+/// # Example
+/// ```rust
+/// use scroll::{ChunkedPread, ChunkedPwrite, Chunks, BE};
+/// let mut chunks = Chunks::new();
+/// chunks.push(vec![0x12, 0x34]);
+/// chunks.push(vec![0x56]);
+/// chunks.push(vec![0x78, 0x9a]);
+/// assert_eq!(chunks.pread_with::<u32>(0, BE).unwrap(), 0x12345678);
+/// chunks.pwrite_with(0xaabbu16, 1, BE).unwrap();
+/// assert_eq!(chunks.chunks(), [vec![0x12, 0xaa], vec![0xbb], vec![0x78, 0x9a]]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Chunks<T> {
+    chunks: Vec<T>,
+    /// The offset each chunk ends at
+    ends: Vec<usize>,
+}
+
+impl<T> Default for Chunks<T> {
+    fn default() -> Self {
+        Chunks {
+            chunks: Vec::new(),
+            ends: Vec::new(),
+        }
+    }
+}
+
+impl<T: AsRef<[u8]>> Chunks<T> {
+    /// An empty rope
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append `chunk` to the end of the rope
+    pub fn push(&mut self, chunk: T) {
+        let end = Chunked::len(self) + chunk.as_ref().len();
+        self.chunks.push(chunk);
+        self.ends.push(end);
+    }
+
+    /// The chunks of the rope, in order
+    pub fn chunks(&self) -> &[T] {
+        &self.chunks
+    }
+
+    /// Consume the rope, returning its chunks
+    pub fn into_chunks(self) -> Vec<T> {
+        self.chunks
+    }
+
+    /// The index of the chunk `offset` is in, and the offset that chunk starts at
+    fn locate(&self, offset: usize) -> Option<(usize, usize)> {
+        let index = self.ends.partition_point(|end| *end <= offset);
+        let chunk = self.chunks.get(index)?;
+        Some((index, self.ends[index] - chunk.as_ref().len()))
+    }
+}
+
+impl<T: AsRef<[u8]>> FromIterator<T> for Chunks<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut chunks = Chunks::new();
+        for chunk in iter {
+            chunks.push(chunk);
+        }
+        chunks
+    }
+}
+
+impl<T: AsRef<[u8]>> Chunked for Chunks<T> {
+    fn len(&self) -> usize {
+        self.ends.last().copied().unwrap_or(0)
+    }
+
+    fn chunk_at(&self, offset: usize) -> &[u8] {
+        match self.locate(offset) {
+            Some((index, begin)) => &self.chunks[index].as_ref()[offset - begin..],
+            None => &[],
+        }
+    }
+}
+
+impl<T: AsRef<[u8]> + AsMut<[u8]>> ChunkedMut for Chunks<T> {
+    fn chunk_at_mut(&mut self, offset: usize) -> &mut [u8] {
+        match self.locate(offset) {
+            Some((index, begin)) => &mut self.chunks[index].as_mut()[offset - begin..],
+            None => &mut [],
+        }
+    }
+}
+
+impl Chunked for VecDeque<u8> {
+    fn len(&self) -> usize {
+        VecDeque::len(self)
+    }
+
+    fn chunk_at(&self, offset: usize) -> &[u8] {
+        let (front, back) = self.as_slices();
+        match offset.checked_sub(front.len()) {
+            None => &front[offset..],
+            Some(offset) => back.get(offset..).unwrap_or(&[]),
+        }
+    }
+}
+
+impl ChunkedMut for VecDeque<u8> {
+    fn chunk_at_mut(&mut self, offset: usize) -> &mut [u8] {
+        let (front, back) = self.as_mut_slices();
+        match offset.checked_sub(front.len()) {
+            None => &mut front[offset..],
+            Some(offset) => back.get_mut(offset..).unwrap_or(&mut []),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::ffi::CString;
+
+    use super::{ChunkedPread, ChunkedPwrite, Chunks};
+    use crate::{Error, Sleb128, BE, LE};
+
+    #[test]
+    fn chunks_read_straddling_values() {
+        let chunks: Chunks<&[u8]> = [&b"\x01\x02"[..], b"", b"\x03\x04hel", b"lo\0", b"\xff"]
+            .into_iter()
+            .collect();
+        let offset = &mut 0;
+        assert_eq!(chunks.gread_with::<u8>(offset, LE).unwrap(), 1);
+        assert_eq!(chunks.gread_with::<u16>(offset, BE).unwrap(), 0x0203);
+        assert_eq!(chunks.gread_with::<u8>(offset, LE).unwrap(), 4);
+        let hello: CString = chunks.gread(offset).unwrap();
+        assert_eq!(hello.as_bytes(), b"hello");
+        assert_eq!(*offset, 10);
+        let err = chunks.pread_with::<u32>(9, LE).unwrap_err();
+        assert_eq!(err.offset(), Some(9));
+        assert!(matches!(
+            chunks.pread_with::<u8>(11, LE),
+            Err(Error::At { offset: 11, .. })
+        ));
+    }
+
+    #[test]
+    fn chunks_write_straddling_values() {
+        let mut chunks: Chunks<Vec<u8>> = vec![vec![0; 1], vec![0; 2], vec![0; 3]]
+            .into_iter()
+            .collect();
+        let offset = &mut 0;
+        chunks.gwrite_with(0xdeadbeefu32, offset, BE).unwrap();
+        chunks.gwrite_with(Sleb128::from(-200), offset, ()).unwrap();
+        assert_eq!(*offset, 6);
+        assert!(chunks.gwrite_with(0u8, offset, LE).is_err());
+        assert!(chunks.pwrite_with(0u32, 3, LE).is_err());
+        assert_eq!(
+            chunks.into_chunks(),
+            [vec![0xde], vec![0xad, 0xbe], vec![0xef, 0xb8, 0x7e]]
+        );
+    }
+
+    #[test]
+    fn vec_deque_wrapped_around() {
+        let mut ring: VecDeque<u8> = VecDeque::with_capacity(8);
+        ring.extend([0xff; 6]);
+        ring.drain(..5);
+        ring.extend([1, 2, 3, 4, 5, 6, 7]);
+        let split = ring.as_slices().0.len();
+        assert!(split > 0 && split < ring.len());
+        assert_eq!(
+            ring.pread_with::<u16>(split - 1, BE).unwrap(),
+            u16::from_be_bytes([ring[split - 1], ring[split]])
+        );
+        ring.pwrite_with(0x1122334455667788u64, 0, LE).unwrap();
+        assert_eq!(ring.pread_with::<u64>(0, LE).unwrap(), 0x1122334455667788);
+        assert!(ring
+            .iter()
+            .eq(&[0x88, 0x77, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11]));
+    }
+}
@@ -178,13 +178,25 @@
 //!     Error::Scroll(error)
 //!   }
 //! }
+//!
+//! // Errors read from buffers must be `Locate`; pass scroll's own errors on to it, so that they
+//! // record where in the buffer they occurred.
+//! impl scroll::Locate for Error {
+//!   fn at(self, offset: usize) -> Error {
+//!     match self {
+//!       Error::Scroll(error) => Error::Scroll(error.at(offset)),
+//!       error => error,
+//!     }
+//!   }
+//! }
 //! ```
 
 use core::mem::size_of;
-use core::mem::transmute;
 use core::ptr::copy_nonoverlapping;
 use core::{result, str};
 #[cfg(feature = "std")]
+use std::borrow::{Cow, ToOwned};
+#[cfg(feature = "std")]
 use std::ffi::{CStr, CString};
 
 use crate::endian::Endian;
@@ -193,9 +205,22 @@ use crate::Pread;
 use crate::Pwrite;
 
 /// A trait for measuring how large something is; for a byte sequence, it will be its length.
+///
+/// Unlike [SizeWith](trait.SizeWith.html) this measures a value, which is how many bytes it takes
+/// up when written with `ctx`, e.g. to allocate the buffer for a struct holding strings or vectors.
 pub trait MeasureWith<Ctx> {
     /// How large is `Self`, given the `ctx`?
     fn measure_with(&self, ctx: &Ctx) -> usize;
+
+    /// How large are the elements of `slice` together, given the `ctx`? Types of a fixed size
+    /// override this, so that measuring e.g. a `Vec<u8>` doesn't go through every element.
+    #[inline]
+    fn measure_slice_with(slice: &[Self], ctx: &Ctx) -> usize
+    where
+        Self: Sized,
+    {
+        slice.iter().map(|elem| elem.measure_with(ctx)).sum()
+    }
 }
 
 impl<Ctx> MeasureWith<Ctx> for [u8] {
@@ -205,10 +230,139 @@ impl<Ctx> MeasureWith<Ctx> for [u8] {
     }
 }
 
-impl<Ctx, T: AsRef<[u8]>> MeasureWith<Ctx> for T {
+impl<Ctx, const N: usize> MeasureWith<Ctx> for [u8; N] {
     #[inline]
     fn measure_with(&self, _ctx: &Ctx) -> usize {
-        self.as_ref().len()
+        N
+    }
+
+    #[inline]
+    fn measure_slice_with(slice: &[Self], _ctx: &Ctx) -> usize {
+        slice.len() * N
+    }
+}
+
+impl<Ctx, T: MeasureWith<Ctx> + ?Sized> MeasureWith<Ctx> for &T {
+    #[inline]
+    fn measure_with(&self, ctx: &Ctx) -> usize {
+        (**self).measure_with(ctx)
+    }
+}
+
+/// The sum of the sizes of the elements, each measured with `ctx`
+#[cfg(feature = "std")]
+impl<Ctx, T: MeasureWith<Ctx>> MeasureWith<Ctx> for Vec<T> {
+    #[inline]
+    fn measure_with(&self, ctx: &Ctx) -> usize {
+        T::measure_slice_with(self, ctx)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Ctx, T: MeasureWith<Ctx> + ?Sized> MeasureWith<Ctx> for Box<T> {
+    #[inline]
+    fn measure_with(&self, ctx: &Ctx) -> usize {
+        (**self).measure_with(ctx)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Ctx, T: MeasureWith<Ctx> + ToOwned + ?Sized> MeasureWith<Ctx> for Cow<'_, T> {
+    #[inline]
+    fn measure_with(&self, ctx: &Ctx) -> usize {
+        (**self).measure_with(ctx)
+    }
+}
+
+macro_rules! measure_impl {
+    ($ty:ty) => {
+        impl<Ctx> MeasureWith<Ctx> for $ty {
+            #[inline]
+            fn measure_with(&self, _ctx: &Ctx) -> usize {
+                size_of::<$ty>()
+            }
+
+            #[inline]
+            fn measure_slice_with(slice: &[Self], _ctx: &Ctx) -> usize {
+                slice.len() * size_of::<$ty>()
+            }
+        }
+    };
+}
+
+measure_impl!(u8);
+measure_impl!(i8);
+measure_impl!(u16);
+measure_impl!(i16);
+measure_impl!(u32);
+measure_impl!(i32);
+measure_impl!(u64);
+measure_impl!(i64);
+measure_impl!(u128);
+measure_impl!(i128);
+measure_impl!(f32);
+measure_impl!(f64);
+measure_impl!(bool);
+
+/// The size of the string's bytes, as written with `()`.
+///
+/// `str` is measured with the context it is written with, so `measure_with(&Default::default())`
+/// can't tell this impl from the one for `StrCtx`; name the context instead. Derived impls
+/// measure a `&str` field with the context of its `#[scroll(ctx = ...)]` attribute, the same one
+/// it is written with.
+impl MeasureWith<()> for str {
+    #[inline]
+    fn measure_with(&self, _ctx: &()) -> usize {
+        self.len()
+    }
+}
+
+/// The size of the string's bytes, like any other byte buffer measured with an `Endian`
+impl MeasureWith<Endian> for str {
+    #[inline]
+    fn measure_with(&self, _ctx: &Endian) -> usize {
+        self.len()
+    }
+}
+
+/// The size of the string as written with `ctx`: followed by the delimiter, or padded to the
+/// fixed length
+impl MeasureWith<StrCtx> for str {
+    #[inline]
+    fn measure_with(&self, ctx: &StrCtx) -> usize {
+        match *ctx {
+            StrCtx::Delimiter(_) => self.len() + 1,
+            StrCtx::DelimiterUntil(_, len) => (self.len() + 1).min(len),
+            StrCtx::Length(len) => len,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Ctx> MeasureWith<Ctx> for String
+where
+    str: MeasureWith<Ctx>,
+{
+    #[inline]
+    fn measure_with(&self, ctx: &Ctx) -> usize {
+        self.as_str().measure_with(ctx)
+    }
+}
+
+/// The size of the string including its terminating `NULL` byte
+#[cfg(feature = "std")]
+impl MeasureWith<()> for CStr {
+    #[inline]
+    fn measure_with(&self, _ctx: &()) -> usize {
+        self.to_bytes_with_nul().len()
+    }
+}
+
+#[cfg(feature = "std")]
+impl MeasureWith<()> for CString {
+    #[inline]
+    fn measure_with(&self, ctx: &()) -> usize {
+        self.as_c_str().measure_with(ctx)
     }
 }
 
@@ -251,6 +405,22 @@ impl StrCtx {
     }
 }
 
+/// A `&str` to be written with a [`StrCtx`], followed by the delimiter or padded to the fixed
+/// length.
+///
+/// `&str` itself is written as its bytes with the `()` context, so that `pwrite(s, offset)` can
+/// infer its context; wrap it in `StrWith` to write it with a `StrCtx` instead. `String`s and the
+/// `&&str` fields written by derived impls can be written with a `StrCtx` directly.
+///
+/// ```rust
+/// use scroll::{ctx::{StrCtx, StrWith}, Pwrite};
+/// let mut bytes = [0xff; 4];
+/// bytes.pwrite_with(StrWith("hi"), 0, StrCtx::Length(3)).unwrap();
+/// assert_eq!(bytes, [b'h', b'i', 0, 0xff]);
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StrWith<'a>(pub &'a str);
+
 /// Reads `Self` from `This` using the context `Ctx`; must _not_ fail
 pub trait FromCtx<Ctx: Copy = (), This: ?Sized = [u8]> {
     fn from_ctx(this: &This, ctx: Ctx) -> Self;
@@ -315,6 +485,9 @@ pub trait FromCtx<Ctx: Copy = (), This: ?Sized = [u8]> {
 ///          }
 ///      }
 ///  }
+///
+///  // doesn't record where errors occurred
+///  impl scroll::Locate for ExternalError {}
 ///  #[derive(Debug, PartialEq, Eq)]
 ///  pub struct Foo(u16);
 ///
@@ -405,11 +578,12 @@ macro_rules! write_into {
     ($typ:ty, $size:expr, $n:expr, $dst:expr, $endian:expr) => {{
         unsafe {
             assert!($dst.len() >= $size);
-            let bytes = transmute::<$typ, [u8; $size]>(if $endian.is_little() {
+            let bytes: [u8; $size] = (if $endian.is_little() {
                 $n.to_le()
             } else {
                 $n.to_be()
-            });
+            })
+            .to_ne_bytes();
             copy_nonoverlapping((&bytes).as_ptr(), $dst.as_mut_ptr(), $size);
         }
     }};
@@ -570,12 +744,12 @@ macro_rules! from_ctx_float_impl {
                         &mut data as *mut signed_to_unsigned!($typ) as *mut u8,
                         $size,
                     );
-                    transmute(if le.is_little() {
-                        data.to_le()
-                    } else {
-                        data.to_be()
-                    })
                 }
+                $typ::from_bits(if le.is_little() {
+                    data.to_le()
+                } else {
+                    data.to_be()
+                })
             }
         }
         impl<'a> TryFromCtx<'a, Endian> for $typ
@@ -712,7 +886,7 @@ where
     }
 }
 
-impl<'a> TryIntoCtx for &'a [u8] {
+impl TryIntoCtx for &[u8] {
     type Error = error::Error;
     #[inline]
     fn try_into_ctx(self, dst: &mut [u8], _ctx: ()) -> error::Result<usize> {
@@ -733,8 +907,7 @@ impl<'a> TryIntoCtx for &'a [u8] {
     }
 }
 
-// TODO: make TryIntoCtx use StrCtx for awesomeness
-impl<'a> TryIntoCtx for &'a str {
+impl TryIntoCtx for &str {
     type Error = error::Error;
     #[inline]
     fn try_into_ctx(self, dst: &mut [u8], _ctx: ()) -> error::Result<usize> {
@@ -743,6 +916,73 @@ impl<'a> TryIntoCtx for &'a str {
     }
 }
 
+/// Writes the string followed by the delimiter, or padded with `NULL` bytes up to the fixed length.
+///
+/// Reading it back with the same `ctx` yields the string again when it is delimited; with
+/// `StrCtx::Length` the padding is read back as part of the string.
+impl TryIntoCtx<StrCtx> for StrWith<'_> {
+    type Error = error::Error;
+    #[inline]
+    fn try_into_ctx(self, dst: &mut [u8], ctx: StrCtx) -> error::Result<usize> {
+        let bytes = self.0.as_bytes();
+        let (len, fill) = match ctx {
+            StrCtx::Delimiter(delimiter) => (bytes.len() + 1, delimiter),
+            StrCtx::DelimiterUntil(delimiter, len) => ((bytes.len() + 1).min(len), delimiter),
+            StrCtx::Length(len) => (len, NULL),
+        };
+        if bytes.len() > len {
+            return Err(error::Error::TooBig {
+                size: bytes.len(),
+                len,
+            });
+        }
+        if !ctx.is_empty() && bytes.contains(&fill) {
+            return Err(error::Error::BadInput {
+                size: bytes.len(),
+                msg: "string contains its delimiter",
+            });
+        }
+        if len > dst.len() {
+            return Err(error::Error::TooBig {
+                size: len,
+                len: dst.len(),
+            });
+        }
+        dst[..bytes.len()].copy_from_slice(bytes);
+        for byte in &mut dst[bytes.len()..len] {
+            *byte = fill;
+        }
+        Ok(len)
+    }
+}
+
+/// So that derived impls, which write a reference to each field, can write `&str` fields
+impl TryIntoCtx<StrCtx> for &&str {
+    type Error = error::Error;
+    #[inline]
+    fn try_into_ctx(self, dst: &mut [u8], ctx: StrCtx) -> error::Result<usize> {
+        StrWith(self).try_into_ctx(dst, ctx)
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryIntoCtx<StrCtx> for &String {
+    type Error = error::Error;
+    #[inline]
+    fn try_into_ctx(self, dst: &mut [u8], ctx: StrCtx) -> error::Result<usize> {
+        StrWith(self).try_into_ctx(dst, ctx)
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryIntoCtx<StrCtx> for String {
+    type Error = error::Error;
+    #[inline]
+    fn try_into_ctx(self, dst: &mut [u8], ctx: StrCtx) -> error::Result<usize> {
+        StrWith(&self).try_into_ctx(dst, ctx)
+    }
+}
+
 // TODO: we can make this compile time without size_of call, but compiler probably does that anyway
 macro_rules! sizeof_impl {
     ($ty:ty) => {
@@ -768,6 +1008,424 @@ sizeof_impl!(i128);
 sizeof_impl!(f32);
 sizeof_impl!(f64);
 
+/// The parsing context for reading a `bool` from a byte
+///
+/// Strict parsing only accepts 0 and 1, and is what reading with an `Endian` does; lenient parsing
+/// takes any nonzero byte to be `true`. Either way `bool`s are written as 0 or 1.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BoolCtx {
+    Strict,
+    Lenient,
+}
+
+impl Default for BoolCtx {
+    #[inline]
+    fn default() -> Self {
+        BoolCtx::Strict
+    }
+}
+
+impl<'a> TryFromCtx<'a, BoolCtx> for bool {
+    type Error = error::Error;
+    #[inline]
+    fn try_from_ctx(src: &'a [u8], ctx: BoolCtx) -> result::Result<(Self, usize), Self::Error> {
+        match (src.first(), ctx) {
+            (None, _) => Err(error::Error::TooBig { size: 1, len: 0 }),
+            (Some(0), _) => Ok((false, 1)),
+            (Some(1), _) | (Some(_), BoolCtx::Lenient) => Ok((true, 1)),
+            (Some(_), BoolCtx::Strict) => Err(error::Error::BadInput {
+                size: 1,
+                msg: "invalid bool, expected 0 or 1",
+            }),
+        }
+    }
+}
+
+impl<'a> TryFromCtx<'a, Endian> for bool {
+    type Error = error::Error;
+    #[inline]
+    fn try_from_ctx(src: &'a [u8], _ctx: Endian) -> result::Result<(Self, usize), Self::Error> {
+        Self::try_from_ctx(src, BoolCtx::Strict)
+    }
+}
+
+impl TryIntoCtx<BoolCtx> for bool {
+    type Error = error::Error;
+    #[inline]
+    fn try_into_ctx(self, dst: &mut [u8], _ctx: BoolCtx) -> error::Result<usize> {
+        dst.pwrite_with(self as u8, 0, Endian::default())
+    }
+}
+
+impl TryIntoCtx<Endian> for bool {
+    type Error = error::Error;
+    #[inline]
+    fn try_into_ctx(self, dst: &mut [u8], _ctx: Endian) -> error::Result<usize> {
+        self.try_into_ctx(dst, BoolCtx::Strict)
+    }
+}
+
+impl TryIntoCtx<BoolCtx> for &bool {
+    type Error = error::Error;
+    #[inline]
+    fn try_into_ctx(self, dst: &mut [u8], ctx: BoolCtx) -> error::Result<usize> {
+        (*self).try_into_ctx(dst, ctx)
+    }
+}
+
+impl TryIntoCtx<Endian> for &bool {
+    type Error = error::Error;
+    #[inline]
+    fn try_into_ctx(self, dst: &mut [u8], ctx: Endian) -> error::Result<usize> {
+        (*self).try_into_ctx(dst, ctx)
+    }
+}
+
+impl SizeWith<BoolCtx> for bool {
+    #[inline]
+    fn size_with(_ctx: &BoolCtx) -> usize {
+        1
+    }
+}
+
+sizeof_impl!(bool);
+
+/// Reads any nonzero byte as `true`, since reading can't fail; read with
+/// [`TryFromCtx`](trait.TryFromCtx.html) to reject anything but 0 and 1
+impl FromCtx<Endian> for bool {
+    #[inline]
+    fn from_ctx(src: &[u8], _ctx: Endian) -> Self {
+        src[0] != 0
+    }
+}
+
+impl IntoCtx<Endian> for bool {
+    #[inline]
+    fn into_ctx(self, dst: &mut [u8], _ctx: Endian) {
+        dst[0] = self as u8;
+    }
+}
+
+impl IntoCtx<Endian> for &bool {
+    #[inline]
+    fn into_ctx(self, dst: &mut [u8], ctx: Endian) {
+        (*self).into_ctx(dst, ctx)
+    }
+}
+
+/// The parsing context for reading and writing a `char`
+///
+/// Reading and writing a `char` with an `Endian` uses UTF-32 in that byte order.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CharCtx {
+    /// Four bytes in the given byte order
+    Utf32(Endian),
+    /// One to four bytes, depending on the `char`
+    Utf8,
+}
+
+impl Default for CharCtx {
+    #[inline]
+    fn default() -> Self {
+        CharCtx::Utf8
+    }
+}
+
+impl<'a> TryFromCtx<'a, CharCtx> for char {
+    type Error = error::Error;
+    #[inline]
+    fn try_from_ctx(src: &'a [u8], ctx: CharCtx) -> result::Result<(Self, usize), Self::Error> {
+        match ctx {
+            CharCtx::Utf32(le) => {
+                let scalar: u32 = src.pread_with(0, le)?;
+                let c = char::from_u32(scalar).ok_or(error::Error::BadInput {
+                    size: 4,
+                    msg: "invalid char, not a unicode scalar value",
+                })?;
+                Ok((c, 4))
+            }
+            CharCtx::Utf8 => {
+                let size = match src.first() {
+                    None => return Err(error::Error::TooBig { size: 1, len: 0 }),
+                    Some(0x00..=0x7f) => 1,
+                    Some(0xc0..=0xdf) => 2,
+                    Some(0xe0..=0xef) => 3,
+                    Some(0xf0..=0xf7) => 4,
+                    Some(_) => 1,
+                };
+                let bytes: &[u8] = src.pread_with(0, size)?;
+                match str::from_utf8(bytes).ok().and_then(|s| s.chars().next()) {
+                    Some(c) => Ok((c, size)),
+                    None => Err(error::Error::BadInput {
+                        size,
+                        msg: "invalid char, not valid UTF-8",
+                    }),
+                }
+            }
+        }
+    }
+}
+
+impl<'a> TryFromCtx<'a, Endian> for char {
+    type Error = error::Error;
+    #[inline]
+    fn try_from_ctx(src: &'a [u8], le: Endian) -> result::Result<(Self, usize), Self::Error> {
+        Self::try_from_ctx(src, CharCtx::Utf32(le))
+    }
+}
+
+impl TryIntoCtx<CharCtx> for char {
+    type Error = error::Error;
+    #[inline]
+    fn try_into_ctx(self, dst: &mut [u8], ctx: CharCtx) -> error::Result<usize> {
+        match ctx {
+            CharCtx::Utf32(le) => dst.pwrite_with(self as u32, 0, le),
+            CharCtx::Utf8 => {
+                let mut buf = [0; 4];
+                let bytes: &[u8] = self.encode_utf8(&mut buf).as_bytes();
+                dst.pwrite(bytes, 0)
+            }
+        }
+    }
+}
+
+impl TryIntoCtx<Endian> for char {
+    type Error = error::Error;
+    #[inline]
+    fn try_into_ctx(self, dst: &mut [u8], le: Endian) -> error::Result<usize> {
+        self.try_into_ctx(dst, CharCtx::Utf32(le))
+    }
+}
+
+impl TryIntoCtx<CharCtx> for &char {
+    type Error = error::Error;
+    #[inline]
+    fn try_into_ctx(self, dst: &mut [u8], ctx: CharCtx) -> error::Result<usize> {
+        (*self).try_into_ctx(dst, ctx)
+    }
+}
+
+impl TryIntoCtx<Endian> for &char {
+    type Error = error::Error;
+    #[inline]
+    fn try_into_ctx(self, dst: &mut [u8], le: Endian) -> error::Result<usize> {
+        (*self).try_into_ctx(dst, le)
+    }
+}
+
+sizeof_impl!(char);
+
+impl MeasureWith<Endian> for char {
+    #[inline]
+    fn measure_with(&self, _ctx: &Endian) -> usize {
+        4
+    }
+
+    #[inline]
+    fn measure_slice_with(slice: &[Self], _ctx: &Endian) -> usize {
+        slice.len() * 4
+    }
+}
+
+/// The size of the `char` as written with `ctx`: four bytes in UTF-32, one to four in UTF-8
+impl MeasureWith<CharCtx> for char {
+    #[inline]
+    fn measure_with(&self, ctx: &CharCtx) -> usize {
+        match *ctx {
+            CharCtx::Utf32(_) => 4,
+            CharCtx::Utf8 => self.len_utf8(),
+        }
+    }
+}
+
+/// Reads UTF-32 in the byte order of the context; since reading can't fail, values which are not
+/// unicode scalar values are read as `char::REPLACEMENT_CHARACTER`, and
+/// [`TryFromCtx`](trait.TryFromCtx.html) rejects them instead
+impl FromCtx<Endian> for char {
+    #[inline]
+    fn from_ctx(src: &[u8], le: Endian) -> Self {
+        char::from_u32(u32::from_ctx(src, le)).unwrap_or(char::REPLACEMENT_CHARACTER)
+    }
+}
+
+impl IntoCtx<Endian> for char {
+    #[inline]
+    fn into_ctx(self, dst: &mut [u8], le: Endian) {
+        (self as u32).into_ctx(dst, le)
+    }
+}
+
+impl IntoCtx<Endian> for &char {
+    #[inline]
+    fn into_ctx(self, dst: &mut [u8], le: Endian) {
+        (*self).into_ctx(dst, le)
+    }
+}
+
+/// The width of pointer sized integers, `usize` and `isize`, in the data being read or written,
+/// which is part of their `(Endian, Width)` context
+///
+/// The width of the host machine is its default.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Width {
+    W16,
+    W32,
+    W64,
+}
+
+impl Width {
+    /// The number of bytes in a pointer sized integer
+    #[inline]
+    pub fn size(self) -> usize {
+        match self {
+            Width::W16 => 2,
+            Width::W32 => 4,
+            Width::W64 => 8,
+        }
+    }
+}
+
+impl Default for Width {
+    #[inline]
+    fn default() -> Self {
+        #[cfg(target_pointer_width = "16")]
+        let width = Width::W16;
+        #[cfg(target_pointer_width = "32")]
+        let width = Width::W32;
+        #[cfg(target_pointer_width = "64")]
+        let width = Width::W64;
+        width
+    }
+}
+
+macro_rules! pointer_sized_impl {
+    ($typ:tt, $w16:tt, $w32:tt, $w64:tt) => {
+        impl<'a> TryFromCtx<'a, (Endian, Width)> for $typ {
+            type Error = error::Error;
+            #[inline]
+            fn try_from_ctx(
+                src: &'a [u8],
+                (le, width): (Endian, Width),
+            ) -> result::Result<(Self, usize), Self::Error> {
+                let n = match width {
+                    Width::W16 => $typ::try_from(src.pread_with::<$w16>(0, le)?).ok(),
+                    Width::W32 => $typ::try_from(src.pread_with::<$w32>(0, le)?).ok(),
+                    Width::W64 => $typ::try_from(src.pread_with::<$w64>(0, le)?).ok(),
+                };
+                let n = n.ok_or(error::Error::BadInput {
+                    size: width.size(),
+                    msg: concat!("value too large for a ", stringify!($typ)),
+                })?;
+                Ok((n, width.size()))
+            }
+        }
+
+        impl TryIntoCtx<(Endian, Width)> for $typ {
+            type Error = error::Error;
+            #[inline]
+            fn try_into_ctx(
+                self,
+                dst: &mut [u8],
+                (le, width): (Endian, Width),
+            ) -> error::Result<usize> {
+                let too_large = |_| error::Error::BadInput {
+                    size: width.size(),
+                    msg: concat!(stringify!($typ), " too large for its width"),
+                };
+                match width {
+                    Width::W16 => dst.pwrite_with($w16::try_from(self).map_err(too_large)?, 0, le),
+                    Width::W32 => dst.pwrite_with($w32::try_from(self).map_err(too_large)?, 0, le),
+                    Width::W64 => dst.pwrite_with($w64::try_from(self).map_err(too_large)?, 0, le),
+                }
+            }
+        }
+
+        impl<'a> TryIntoCtx<(Endian, Width)> for &'a $typ {
+            type Error = error::Error;
+            #[inline]
+            fn try_into_ctx(self, dst: &mut [u8], ctx: (Endian, Width)) -> error::Result<usize> {
+                (*self).try_into_ctx(dst, ctx)
+            }
+        }
+
+        impl SizeWith<(Endian, Width)> for $typ {
+            #[inline]
+            fn size_with(&(_, width): &(Endian, Width)) -> usize {
+                width.size()
+            }
+        }
+
+        impl MeasureWith<(Endian, Width)> for $typ {
+            #[inline]
+            fn measure_with(&self, &(_, width): &(Endian, Width)) -> usize {
+                width.size()
+            }
+
+            #[inline]
+            fn measure_slice_with(slice: &[Self], &(_, width): &(Endian, Width)) -> usize {
+                slice.len() * width.size()
+            }
+        }
+    };
+}
+
+pointer_sized_impl!(usize, u16, u32, u64);
+pointer_sized_impl!(isize, i16, i32, i64);
+
+// Only for the contexts of scroll, so that other crates can still implement `()` for theirs
+macro_rules! unit_impl {
+    ($ctx:ty) => {
+        /// Reads nothing
+        impl<'a> TryFromCtx<'a, $ctx> for () {
+            type Error = error::Error;
+            #[inline]
+            fn try_from_ctx(
+                _src: &'a [u8],
+                _ctx: $ctx,
+            ) -> result::Result<(Self, usize), Self::Error> {
+                Ok(((), 0))
+            }
+        }
+
+        /// Writes nothing
+        impl TryIntoCtx<$ctx> for () {
+            type Error = error::Error;
+            #[inline]
+            fn try_into_ctx(self, _dst: &mut [u8], _ctx: $ctx) -> error::Result<usize> {
+                Ok(0)
+            }
+        }
+
+        impl TryIntoCtx<$ctx> for &() {
+            type Error = error::Error;
+            #[inline]
+            fn try_into_ctx(self, _dst: &mut [u8], _ctx: $ctx) -> error::Result<usize> {
+                Ok(0)
+            }
+        }
+
+        impl FromCtx<$ctx> for () {
+            #[inline]
+            fn from_ctx(_src: &[u8], _ctx: $ctx) -> Self {}
+        }
+
+        impl IntoCtx<$ctx> for () {
+            #[inline]
+            fn into_ctx(self, _dst: &mut [u8], _ctx: $ctx) {}
+        }
+
+        impl SizeWith<$ctx> for () {
+            #[inline]
+            fn size_with(_ctx: &$ctx) -> usize {
+                0
+            }
+        }
+    };
+}
+
+unit_impl!(Endian);
+unit_impl!(());
+
 impl<'a> TryFromCtx<'a, usize> for &'a [u8] {
     type Error = error::Error;
     #[inline]
@@ -793,7 +1451,7 @@ impl<'a> TryFromCtx<'a> for &'a CStr {
             None => {
                 return Err(error::Error::BadInput {
                     size: 0,
-                    msg: "The input doesn't contain a null byte",
+                    msg: error::NO_NULL_BYTE,
                 })
             }
         };
@@ -814,7 +1472,7 @@ impl<'a> TryFromCtx<'a> for CString {
 }
 
 #[cfg(feature = "std")]
-impl<'a> TryIntoCtx for &'a CStr {
+impl TryIntoCtx for &CStr {
     type Error = error::Error;
     #[inline]
     fn try_into_ctx(self, dst: &mut [u8], _ctx: ()) -> error::Result<usize> {
@@ -843,6 +1501,109 @@ impl TryIntoCtx for CString {
         self.as_c_str().try_into_ctx(dst, ())
     }
 }
+/// Reads as many `T`s as the count in the context, one after the other, each with the element
+/// context; errors are located at the element they occurred in.
+#[cfg(feature = "std")]
+impl<'a, T, Ctx> TryFromCtx<'a, (usize, Ctx)> for Vec<T>
+where
+    T: TryFromCtx<'a, Ctx>,
+    T::Error: From<error::Error> + error::Locate,
+    Ctx: Copy,
+{
+    type Error = T::Error;
+    #[inline]
+    fn try_from_ctx(
+        src: &'a [u8],
+        (count, ctx): (usize, Ctx),
+    ) -> result::Result<(Self, usize), Self::Error> {
+        // the count may be bogus, so don't reserve more elements than there are bytes
+        let mut vec = Vec::with_capacity(count.min(src.len()));
+        let offset = &mut 0;
+        for i in 0..count {
+            let elem = Pread::<Ctx, T::Error>::gread_with(src, offset, ctx)
+                .map_err(|err| error::Locate::in_element(err, i))?;
+            vec.push(elem);
+        }
+        Ok((vec, *offset))
+    }
+}
+
+/// Writes `elems` one after the other into `dst`, each with `ctx`
+fn try_into_ctx_all<N, Ctx, I>(
+    elems: I,
+    dst: &mut [u8],
+    ctx: Ctx,
+) -> result::Result<usize, N::Error>
+where
+    I: IntoIterator<Item = N>,
+    N: TryIntoCtx<Ctx>,
+    N::Error: From<error::Error> + error::Locate,
+    Ctx: Copy,
+{
+    let offset = &mut 0;
+    for (i, elem) in elems.into_iter().enumerate() {
+        dst.gwrite_with(elem, offset, ctx)
+            .map_err(|err| error::Locate::in_element(err, i))?;
+    }
+    Ok(*offset)
+}
+
+/// Writes the elements one after the other, each with the context
+#[cfg(feature = "std")]
+impl<T, Ctx> TryIntoCtx<Ctx> for Vec<T>
+where
+    T: TryIntoCtx<Ctx>,
+    T::Error: From<error::Error> + error::Locate,
+    Ctx: Copy,
+{
+    type Error = T::Error;
+    #[inline]
+    fn try_into_ctx(self, dst: &mut [u8], ctx: Ctx) -> result::Result<usize, Self::Error> {
+        try_into_ctx_all(self, dst, ctx)
+    }
+}
+
+/// Writes the elements one after the other, each with the context
+#[cfg(feature = "std")]
+impl<'a, T, Ctx> TryIntoCtx<Ctx> for &'a Vec<T>
+where
+    &'a T: TryIntoCtx<Ctx>,
+    <&'a T as TryIntoCtx<Ctx>>::Error: From<error::Error> + error::Locate,
+    Ctx: Copy,
+{
+    type Error = <&'a T as TryIntoCtx<Ctx>>::Error;
+    #[inline]
+    fn try_into_ctx(self, dst: &mut [u8], ctx: Ctx) -> result::Result<usize, Self::Error> {
+        try_into_ctx_all(self, dst, ctx)
+    }
+}
+
+// Slices of every type can't be written generically, because `&[u8]` could then be written both
+// as raw bytes and as `u8`s with an `Endian`, and `pwrite(bytes, offset)` couldn't infer which
+macro_rules! slice_into_ctx_impl {
+    ($typ:ty) => {
+        impl<'a> TryIntoCtx<Endian> for &'a [$typ] {
+            type Error = error::Error;
+            #[inline]
+            fn try_into_ctx(self, dst: &mut [u8], le: Endian) -> error::Result<usize> {
+                try_into_ctx_all(self, dst, le)
+            }
+        }
+    };
+}
+
+slice_into_ctx_impl!(i8);
+slice_into_ctx_impl!(u16);
+slice_into_ctx_impl!(i16);
+slice_into_ctx_impl!(u32);
+slice_into_ctx_impl!(i32);
+slice_into_ctx_impl!(u64);
+slice_into_ctx_impl!(i64);
+slice_into_ctx_impl!(u128);
+slice_into_ctx_impl!(i128);
+slice_into_ctx_impl!(f32);
+slice_into_ctx_impl!(f64);
+
 impl<'a, const N: usize> TryFromCtx<'a> for [u8; N] {
     type Error = error::Error;
     fn try_from_ctx(from: &'a [u8], _ctx: ()) -> Result<(Self, usize), Self::Error> {
@@ -856,7 +1617,7 @@ impl<const N: usize> TryIntoCtx for [u8; N] {
         from.pwrite(self.as_slice(), 0)
     }
 }
-impl<'a, const N: usize> TryIntoCtx for &'a [u8; N] {
+impl<const N: usize> TryIntoCtx for &[u8; N] {
     type Error = error::Error;
     fn try_into_ctx(self, from: &mut [u8], ctx: ()) -> Result<usize, Self::Error> {
         (*self).try_into_ctx(from, ctx)
@@ -891,6 +1652,19 @@ mod tests {
         assert_eq!(got, src.as_c_str());
     }
 
+    #[test]
+    fn cstr_without_null_byte() {
+        let err = <&CStr as TryFromCtx>::try_from_ctx(b"abc", ()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "bad input The input doesn't contain a null byte (0)"
+        );
+        // readers fetching their input on demand keep asking for one more byte
+        assert_eq!(error::Locate::needed_len(&err, 3), Some(4));
+        let err = b"abc".pread::<CString>(0).unwrap_err();
+        assert_eq!(error::Locate::needed_len(&err, 3), Some(4));
+    }
+
     #[test]
     fn round_trip_a_c_str() {
         let src = CString::new("Hello World").unwrap();
@@ -906,4 +1680,195 @@ mod tests {
         assert_eq!(bytes_read, as_bytes.len());
         assert_eq!(got, src);
     }
+
+    #[test]
+    fn round_trip_a_str_with_str_ctx() {
+        let ctxs = [
+            StrCtx::Delimiter(NULL),
+            StrCtx::DelimiterUntil(SPACE, 8),
+            StrCtx::Length(5),
+        ];
+        for ctx in ctxs {
+            let mut buffer = [0xff; 8];
+            let written = buffer.pwrite_with(StrWith("hello"), 0, ctx).unwrap();
+            let (got, read) = <&str as TryFromCtx<StrCtx>>::try_from_ctx(&buffer, ctx).unwrap();
+            assert_eq!(got, "hello");
+            assert_eq!(written, 5 + ctx.len());
+            assert_eq!(read, written);
+        }
+    }
+
+    #[test]
+    fn write_a_str_with_str_ctx() {
+        let mut buffer = [0xff; 8];
+        assert_eq!(
+            buffer
+                .pwrite_with(StrWith("hi"), 0, StrCtx::Length(4))
+                .unwrap(),
+            4
+        );
+        assert_eq!(buffer, [b'h', b'i', 0, 0, 0xff, 0xff, 0xff, 0xff]);
+        assert_eq!(
+            buffer
+                .pwrite_with(StrWith("hi"), 0, StrCtx::Delimiter(SPACE))
+                .unwrap(),
+            3
+        );
+        assert_eq!(buffer[..3], [b'h', b'i', SPACE]);
+        assert_eq!(
+            buffer
+                .pwrite_with(String::from("hi"), 0, StrCtx::default())
+                .unwrap(),
+            3
+        );
+        assert_eq!(buffer[..3], [b'h', b'i', NULL]);
+        // a string filling the maximum length is not followed by the delimiter
+        let written = buffer.pwrite_with(StrWith("hello"), 0, StrCtx::DelimiterUntil(SPACE, 5));
+        assert_eq!(written.unwrap(), 5);
+        assert_eq!(buffer[..6], [b'h', b'e', b'l', b'l', b'o', 0xff]);
+
+        assert!(buffer
+            .pwrite_with(StrWith("hello"), 0, StrCtx::Length(4))
+            .is_err());
+        assert!(buffer
+            .pwrite_with(StrWith("hello"), 0, StrCtx::DelimiterUntil(NULL, 4))
+            .is_err());
+        assert!(buffer
+            .pwrite_with(StrWith("hi there"), 0, StrCtx::Delimiter(SPACE))
+            .is_err());
+        assert!(buffer
+            .pwrite_with(StrWith("12345678"), 0, StrCtx::Delimiter(NULL))
+            .is_err());
+    }
+
+    #[test]
+    fn bool_char_and_pointer_sized() {
+        let bytes = [0, 1, 2];
+        assert!(!bytes.pread_with::<bool>(0, Endian::Little).unwrap());
+        assert!(bytes.pread_with::<bool>(1, BoolCtx::Strict).unwrap());
+        assert!(bytes.pread_with::<bool>(2, Endian::Big).is_err());
+        assert!(bytes.pread_with::<bool>(2, BoolCtx::Lenient).unwrap());
+
+        let mut buffer = [0xffu8; 8];
+        assert_eq!(buffer.pwrite_with(true, 0, Endian::Big).unwrap(), 1);
+        assert_eq!(buffer.pwrite_with('é', 1, CharCtx::Utf8).unwrap(), 2);
+        assert_eq!(buffer.pwrite_with('→', 3, Endian::Big).unwrap(), 4);
+        assert_eq!(buffer, [1, 0xc3, 0xa9, 0, 0, 0x21, 0x92, 0xff]);
+        assert_eq!(buffer.pread_with::<char>(1, CharCtx::Utf8).unwrap(), 'é');
+        assert_eq!(buffer.pread_with::<char>(3, Endian::Big).unwrap(), '→');
+        // a lone continuation byte, a truncated sequence, and a surrogate
+        assert!(buffer.pread_with::<char>(2, CharCtx::Utf8).is_err());
+        assert!(buffer[..2].pread_with::<char>(1, CharCtx::Utf8).is_err());
+        assert!([0, 0xd8, 0, 0]
+            .pread_with::<char>(0, CharCtx::Utf32(Endian::Little))
+            .is_err());
+
+        let ctx = (Endian::Little, Width::W32);
+        assert_eq!(usize::size_with(&ctx), 4);
+        assert_eq!(buffer.pwrite_with(0xdead_usize, 0, ctx).unwrap(), 4);
+        assert_eq!(buffer.pread_with::<usize>(0, ctx).unwrap(), 0xdead);
+        assert_eq!(
+            buffer
+                .pwrite_with(-2isize, 0, (Endian::Big, Width::W16))
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            buffer
+                .pread_with::<isize>(0, (Endian::Big, Width::W16))
+                .unwrap(),
+            -2
+        );
+        assert!(buffer
+            .pwrite_with(0x10000_usize, 0, (Endian::Big, Width::W16))
+            .is_err());
+        assert_eq!(
+            buffer
+                .pread_with::<usize>(0, (Endian::Big, Width::W16))
+                .unwrap(),
+            0xfffe
+        );
+
+        assert_eq!(buffer.pread_with::<()>(0, Endian::Big).unwrap(), ());
+        assert_eq!(buffer.pwrite_with((), 0, ()).unwrap(), 0);
+        assert_eq!(<() as SizeWith<Endian>>::size_with(&Endian::Big), 0);
+    }
+
+    #[test]
+    fn round_trip_a_vec_with_count() {
+        let mut buffer = [0u8; 8];
+        let words = vec![0xdeadu16, 0xbeef, 0x0102];
+        assert_eq!(buffer.pwrite_with(&words, 1, Endian::Big).unwrap(), 6);
+        assert_eq!(
+            buffer.pwrite_with(&words[1..], 0, Endian::Little).unwrap(),
+            4
+        );
+        assert_eq!(buffer, [0xef, 0xbe, 0x02, 0x01, 0xef, 0x01, 0x02, 0]);
+
+        let read: Vec<u16> = buffer.pread_with(1, (3, Endian::Big)).unwrap();
+        assert_eq!(read, [0xbe02, 0x01ef, 0x0102]);
+        let none: Vec<u16> = buffer.pread_with(8 - 1, (0, Endian::Big)).unwrap();
+        assert!(none.is_empty());
+        let names: Vec<&str> = b"ab\0c\0"
+            .pread_with(0, (2, StrCtx::Delimiter(NULL)))
+            .unwrap();
+        assert_eq!(names, ["ab", "c"]);
+        assert_eq!(buffer.pwrite_with(&names, 0, StrCtx::Length(3)).unwrap(), 6);
+        assert_eq!(buffer[..6], [b'a', b'b', 0, b'c', 0, 0]);
+
+        let err = buffer
+            .pread_with::<Vec<u32>>(2, (2, Endian::Big))
+            .unwrap_err();
+        assert_eq!(err.offset(), Some(6));
+        let err = buffer
+            .pwrite_with(vec![0u32; 3], 0, Endian::Big)
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            error::Error::At {
+                error: error::Cause::BadOffset(8),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn measure_strings_and_vecs() {
+        assert_eq!("hello".measure_with(&StrCtx::Delimiter(0)), 6);
+        assert_eq!("hello".measure_with(&StrCtx::DelimiterUntil(0, 4)), 4);
+        assert_eq!("hello".measure_with(&StrCtx::Length(8)), 8);
+        assert_eq!(String::from("hi").measure_with(&()), 2);
+        let cstr = CString::new("hi").unwrap();
+        assert_eq!(cstr.measure_with(&()), 3);
+        assert_eq!(vec![1u32, 2, 3].measure_with(&Endian::Little), 12);
+        let names = vec!["a", "bc"];
+        assert_eq!(names.measure_with(&StrCtx::default()), 5);
+        let leb = [0xe5u8, 0x8e, 0x26].pread::<crate::Uleb128>(0).unwrap();
+        assert_eq!(leb.measure_with(&()), 3);
+    }
+
+    #[test]
+    fn measure_byte_buffers() {
+        assert_eq!(vec![0u8; 5].measure_with(&()), 5);
+        assert_eq!(vec![[0u8; 4]; 3].measure_with(&Endian::Big), 12);
+        let boxed: Box<[u8]> = vec![0u8; 7].into_boxed_slice();
+        assert_eq!(boxed.measure_with(&()), 7);
+        let cow: Cow<[u8]> = Cow::Borrowed(&[1, 2, 3]);
+        assert_eq!(cow.measure_with(&Endian::Little), 3);
+        let cow: Cow<str> = Cow::Owned(String::from("hey"));
+        assert_eq!(cow.measure_with(&StrCtx::Delimiter(0)), 4);
+        assert_eq!("hey".measure_with(&Endian::Big), 3);
+        assert_eq!(String::from("hey").measure_with(&Endian::Big), 3);
+    }
+
+    #[test]
+    fn measure_chars_and_pointer_sized_integers() {
+        assert_eq!('é'.measure_with(&Endian::Little), 4);
+        assert_eq!('é'.measure_with(&CharCtx::Utf8), 2);
+        assert_eq!('a'.measure_with(&CharCtx::Utf8), 1);
+        assert_eq!(vec!['a', 'b'].measure_with(&Endian::Big), 8);
+        let ctx = (Endian::Little, Width::W32);
+        assert_eq!(1usize.measure_with(&ctx), 4);
+        assert_eq!(vec![-1isize; 3].measure_with(&(Endian::Big, Width::W16)), 6);
+    }
 }
@@ -0,0 +1,295 @@
+use core::result;
+
+use crate::ctx::{TryFromCtx, TryIntoCtx};
+use crate::{error, Pread, Pwrite};
+
+/// A reader bundling a byte slice with the offset it is at and the context values are read with,
+/// so neither has to be passed around separately.
+///
+/// Every read goes through [gread_with](trait.Pread.html#method.gread_with) on the underlying
+/// slice and advances the cursor past the value read.
+///
+/// # Example
+/// ```rust
+/// use scroll::{ctx::StrCtx, Cursor, BE};
+/// let bytes = [0xde, 0xad, 0x00, 0x00, b'h', b'i', 0x01, 0x02];
+/// let mut cursor = Cursor::new(&bytes, BE);
+/// assert_eq!(cursor.read::<u16>().unwrap(), 0xdead);
+/// cursor.align_to(4).unwrap();
+/// let hi: &str = cursor.read_with(StrCtx::Length(2)).unwrap();
+/// assert_eq!(hi, "hi");
+/// assert_eq!(cursor.peek::<u8>().unwrap(), 0x01);
+/// assert_eq!(cursor.remaining(), 2);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Cursor<'a, Ctx> {
+    bytes: &'a [u8],
+    offset: usize,
+    ctx: Ctx,
+}
+
+/// A writer bundling a mutable byte slice with the offset it is at and the context values are
+/// written with; the writing counterpart of [Cursor](struct.Cursor.html).
+///
+/// Every write goes through [gwrite_with](trait.Pwrite.html#method.gwrite_with) on the underlying
+/// slice and advances the cursor past the value written.
+///
+/// # Example
+/// ```rust
+/// use scroll::{CursorMut, LE};
+/// let mut bytes = [0u8; 8];
+/// let mut cursor = CursorMut::new(&mut bytes, LE);
+/// cursor.write(0xbeefu16).unwrap();
+/// cursor.skip(2).unwrap();
+/// cursor.write(0xdeadu16).unwrap();
+/// assert_eq!(cursor.offset(), 6);
+/// assert_eq!(bytes, [0xef, 0xbe, 0, 0, 0xad, 0xde, 0, 0]);
+/// ```
+#[derive(Debug)]
+pub struct CursorMut<'a, Ctx> {
+    bytes: &'a mut [u8],
+    offset: usize,
+    ctx: Ctx,
+}
+
+/// Positioning shared by both cursors
+macro_rules! cursor_common_impl {
+    ($cursor:ident, $bytes:ty) => {
+        impl<'a, Ctx: Copy> $cursor<'a, Ctx> {
+            /// Create a cursor at the start of `bytes`, reading and writing with `ctx` by default
+            #[inline]
+            pub fn new(bytes: $bytes, ctx: Ctx) -> Self {
+                $cursor {
+                    bytes,
+                    offset: 0,
+                    ctx,
+                }
+            }
+
+            /// The offset of the cursor from the start of its bytes
+            #[inline]
+            pub fn offset(&self) -> usize {
+                self.offset
+            }
+
+            /// The context values are read and written with by default
+            #[inline]
+            pub fn ctx(&self) -> Ctx {
+                self.ctx
+            }
+
+            /// How many bytes are left after the cursor
+            #[inline]
+            pub fn remaining(&self) -> usize {
+                self.bytes.len().saturating_sub(self.offset)
+            }
+
+            /// Move the cursor to `offset`, which may be at most the length of its bytes
+            #[inline]
+            pub fn seek(&mut self, offset: usize) -> error::Result<()> {
+                if offset > self.bytes.len() {
+                    return Err(error::Error::BadOffset(offset));
+                }
+                self.offset = offset;
+                Ok(())
+            }
+
+            /// Advance the cursor by `count` bytes
+            #[inline]
+            pub fn skip(&mut self, count: usize) -> error::Result<()> {
+                let offset = self
+                    .offset
+                    .checked_add(count)
+                    .ok_or(error::Error::BadOffset(self.offset))?;
+                self.seek(offset)
+            }
+
+            /// Advance the cursor to the next offset which is a multiple of `alignment`, unless it
+            /// already is at one
+            ///
+            /// # Panics
+            /// If `alignment` is zero
+            #[inline]
+            pub fn align_to(&mut self, alignment: usize) -> error::Result<()> {
+                match self.offset % alignment {
+                    0 => Ok(()),
+                    misalignment => self.skip(alignment - misalignment),
+                }
+            }
+        }
+    };
+}
+
+cursor_common_impl!(Cursor, &'a [u8]);
+cursor_common_impl!(CursorMut, &'a mut [u8]);
+
+impl<'a, Ctx: Copy> Cursor<'a, Ctx> {
+    /// Read a `N` with the default context of the cursor and advance past it
+    #[inline]
+    pub fn read<N>(&mut self) -> result::Result<N, N::Error>
+    where
+        N: TryFromCtx<'a, Ctx>,
+        N::Error: From<error::Error> + error::Locate,
+    {
+        self.read_with(self.ctx)
+    }
+
+    /// Read a `N` with `ctx` and advance past it
+    #[inline]
+    pub fn read_with<N, C>(&mut self, ctx: C) -> result::Result<N, N::Error>
+    where
+        N: TryFromCtx<'a, C>,
+        N::Error: From<error::Error> + error::Locate,
+        C: Copy,
+    {
+        self.bytes.gread_with(&mut self.offset, ctx)
+    }
+
+    /// Read a `N` with the default context of the cursor without advancing
+    #[inline]
+    pub fn peek<N>(&self) -> result::Result<N, N::Error>
+    where
+        N: TryFromCtx<'a, Ctx>,
+        N::Error: From<error::Error> + error::Locate,
+    {
+        self.peek_with(self.ctx)
+    }
+
+    /// Read a `N` with `ctx` without advancing
+    #[inline]
+    pub fn peek_with<N, C>(&self, ctx: C) -> result::Result<N, N::Error>
+    where
+        N: TryFromCtx<'a, C>,
+        N::Error: From<error::Error> + error::Locate,
+        C: Copy,
+    {
+        self.bytes.pread_with(self.offset, ctx)
+    }
+
+    /// Split off the next `len` bytes into a cursor of their own, with the same context, and
+    /// advance past them
+    #[inline]
+    pub fn sub_cursor(&mut self, len: usize) -> error::Result<Cursor<'a, Ctx>> {
+        let bytes: &'a [u8] = self.bytes.gread_with(&mut self.offset, len)?;
+        Ok(Cursor::new(bytes, self.ctx))
+    }
+}
+
+impl<'a, Ctx: Copy> CursorMut<'a, Ctx> {
+    /// Write `n` with the default context of the cursor and advance past it
+    #[inline]
+    pub fn write<N>(&mut self, n: N) -> result::Result<usize, N::Error>
+    where
+        N: TryIntoCtx<Ctx>,
+        N::Error: From<error::Error> + error::Locate,
+    {
+        self.write_with(n, self.ctx)
+    }
+
+    /// Write `n` with `ctx` and advance past it
+    #[inline]
+    pub fn write_with<N, C>(&mut self, n: N, ctx: C) -> result::Result<usize, N::Error>
+    where
+        N: TryIntoCtx<C>,
+        N::Error: From<error::Error> + error::Locate,
+        C: Copy,
+    {
+        self.bytes.gwrite_with(n, &mut self.offset, ctx)
+    }
+
+    /// Read a `N` with the default context of the cursor and advance past it
+    #[inline]
+    pub fn read<'b, N>(&'b mut self) -> result::Result<N, N::Error>
+    where
+        N: TryFromCtx<'b, Ctx>,
+        N::Error: From<error::Error> + error::Locate,
+    {
+        let ctx = self.ctx;
+        self.bytes.gread_with(&mut self.offset, ctx)
+    }
+
+    /// Borrow the next `len` bytes as a cursor of their own, with the same context, and advance
+    /// past them
+    #[inline]
+    pub fn sub_cursor(&mut self, len: usize) -> error::Result<CursorMut<'_, Ctx>> {
+        if len > self.remaining() {
+            return Err(error::Error::TooBig {
+                size: len,
+                len: self.remaining(),
+            });
+        }
+        let start = self.offset;
+        self.offset += len;
+        Ok(CursorMut::new(
+            &mut self.bytes[start..start + len],
+            self.ctx,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cursor, CursorMut};
+    use crate::ctx::StrCtx;
+    use crate::{Uleb128, BE, LE};
+
+    #[test]
+    fn cursor_reads_and_positions() {
+        let bytes = [0x01, 0x02, 0x03, 0x04, 0x85, 0x01, b'a', b'b', 0x00, 0xff];
+        let mut cursor = Cursor::new(&bytes[..], LE);
+        assert_eq!(cursor.peek::<u16>().unwrap(), 0x0201);
+        assert_eq!(cursor.read::<u16>().unwrap(), 0x0201);
+        assert_eq!(cursor.read_with::<u16, _>(BE).unwrap(), 0x0304);
+        assert_eq!(u64::from(cursor.read_with::<Uleb128, _>(()).unwrap()), 133);
+        let ab: &str = cursor.read_with(StrCtx::Delimiter(0)).unwrap();
+        assert_eq!(ab, "ab");
+        assert_eq!(cursor.offset(), 9);
+        assert_eq!(cursor.remaining(), 1);
+
+        cursor.seek(1).unwrap();
+        cursor.align_to(4).unwrap();
+        assert_eq!(cursor.offset(), 4);
+        cursor.align_to(4).unwrap();
+        assert_eq!(cursor.offset(), 4);
+        cursor.skip(6).unwrap();
+        assert_eq!(cursor.remaining(), 0);
+        assert!(cursor.read::<u8>().is_err());
+        assert!(cursor.skip(1).is_err());
+        assert!(cursor.seek(11).is_err());
+        assert_eq!(cursor.offset(), 10);
+    }
+
+    #[test]
+    fn cursor_sub_cursor() {
+        let bytes = [0x00, 0x01, 0x00, 0x02, 0x00, 0x03];
+        let mut cursor = Cursor::new(&bytes[..], BE);
+        cursor.skip(2).unwrap();
+        let mut sub = cursor.sub_cursor(2).unwrap();
+        assert_eq!(cursor.read::<u16>().unwrap(), 3);
+        assert_eq!(sub.read::<u16>().unwrap(), 2);
+        assert_eq!(sub.remaining(), 0);
+        assert!(sub.read::<u8>().is_err());
+        assert!(cursor.sub_cursor(1).is_err());
+    }
+
+    #[test]
+    fn cursor_mut_writes() {
+        let mut bytes = [0u8; 8];
+        {
+            let mut cursor = CursorMut::new(&mut bytes[..], BE);
+            cursor.write(0xdeadu16).unwrap();
+            cursor.align_to(4).unwrap();
+            {
+                let mut sub = cursor.sub_cursor(2).unwrap();
+                sub.write_with(0x1234u16, LE).unwrap();
+                assert!(sub.write(0u8).is_err());
+            }
+            assert_eq!(cursor.write_with(&[7u8, 8][..], ()).unwrap(), 2);
+            assert!(cursor.write(0u8).is_err());
+            cursor.seek(4).unwrap();
+            assert_eq!(cursor.read::<u16>().unwrap(), 0x3412);
+            assert!(cursor.sub_cursor(3).is_err());
+        }
+        assert_eq!(bytes, [0xde, 0xad, 0, 0, 0x34, 0x12, 7, 8]);
+    }
+}
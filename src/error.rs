@@ -17,6 +17,12 @@ pub enum Error {
         size: usize,
         msg: &'static str,
     },
+    /// A derived enum read a tag none of its variants has; `size` is the size of the tag, and signed
+    /// tags are sign extended
+    BadTag {
+        size: usize,
+        tag: u64,
+    },
     /// A custom Scroll error for reporting messages to clients.
     /// For no-std, use [`Error::BadInput`] with a static string.
     #[cfg(feature = "std")]
@@ -24,6 +30,333 @@ pub enum Error {
     /// Returned when IO based errors are encountered
     #[cfg(feature = "std")]
     IO(io::Error),
+    /// `error` occurred reading the value at `offset` of the buffer read from, inside of the field
+    /// at `path` if the value is part of a derived type
+    At {
+        offset: usize,
+        path: Path,
+        error: Cause,
+    },
+}
+
+/// The errors [`Error::At`] locates; these are the errors scroll itself reports, and are available
+/// without `std`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cause {
+    /// See [`Error::TooBig`]
+    TooBig { size: usize, len: usize },
+    /// See [`Error::BadOffset`]
+    BadOffset(usize),
+    /// See [`Error::BadInput`]
+    BadInput { size: usize, msg: &'static str },
+    /// See [`Error::BadTag`]
+    BadTag { size: usize, tag: u64 },
+}
+
+impl Display for Cause {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Cause::TooBig { ref size, ref len } => {
+                write!(fmt, "type is too big ({size}) for {len}")
+            }
+            Cause::BadOffset(ref offset) => {
+                write!(fmt, "bad offset {offset}")
+            }
+            Cause::BadInput { ref msg, ref size } => {
+                write!(fmt, "bad input {msg} ({size})")
+            }
+            Cause::BadTag { ref tag, ref size } => {
+                write!(fmt, "unknown tag {tag:#x} ({size})")
+            }
+        }
+    }
+}
+
+/// How many fields a [`Path`] holds; the outermost ones are elided from deeper paths.
+const PATH_DEPTH: usize = 3;
+
+/// Marks a field of a [`Path`] which is not an element of a sequence.
+const NO_INDEX: u32 = u32::MAX;
+
+/// A step in a [`Path`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathSegment {
+    /// A named field, or the index of a tuple field
+    Field(&'static str),
+    /// An element of a `Vec` or array
+    Index(usize),
+}
+
+/// The path from a derived type to the field an error occurred in, displayed like
+/// `Header.sections[3].name`.
+///
+/// Paths are kept inline so they need no allocation; only the innermost few fields are recorded,
+/// and the elided outer ones are displayed as `…`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Path {
+    ty: Option<&'static str>,
+    /// Innermost field first
+    fields: [&'static str; PATH_DEPTH],
+    /// The element of the sequence in the field at the same position, if any
+    indices: [u32; PATH_DEPTH],
+    len: u8,
+    elided: bool,
+}
+
+impl Default for Path {
+    fn default() -> Self {
+        Path {
+            ty: None,
+            fields: [""; PATH_DEPTH],
+            indices: [NO_INDEX; PATH_DEPTH],
+            len: 0,
+            elided: false,
+        }
+    }
+}
+
+impl Path {
+    /// The outermost derived type the error occurred in, e.g. `Header`
+    pub fn type_name(&self) -> Option<&'static str> {
+        self.ty
+    }
+
+    /// The recorded steps from the type to the field, outermost first; an index comes first when
+    /// the type was read as an element of a sequence outside of any field, e.g. `[3].name`
+    pub fn segments(&self) -> impl Iterator<Item = PathSegment> + '_ {
+        let outer = match self.indices.get(self.len as usize) {
+            Some(&index) if index != NO_INDEX => Some(PathSegment::Index(index as usize)),
+            _ => None,
+        };
+        outer
+            .into_iter()
+            .chain((0..self.len as usize).rev().flat_map(move |i| {
+                let index = match self.indices[i] {
+                    NO_INDEX => None,
+                    index => Some(PathSegment::Index(index as usize)),
+                };
+                Some(PathSegment::Field(self.fields[i]))
+                    .into_iter()
+                    .chain(index)
+            }))
+    }
+
+    /// Whether outer steps of the path were not recorded
+    pub fn is_elided(&self) -> bool {
+        self.elided
+    }
+
+    fn push_field(&mut self, field: &'static str) {
+        match self.fields.get_mut(self.len as usize) {
+            Some(slot) => {
+                *slot = field;
+                self.len += 1;
+            }
+            None => self.elided = true,
+        }
+    }
+
+    /// Records `index` for the field pushed next, or as the outermost step if none is
+    fn push_index(&mut self, index: usize) {
+        match self.indices.get_mut(self.len as usize) {
+            Some(slot) => *slot = u32::try_from(index).unwrap_or(NO_INDEX - 1),
+            None => self.elided = true,
+        }
+    }
+}
+
+impl Display for Path {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let mut first = true;
+        if let Some(ty) = self.ty {
+            fmt.write_str(ty)?;
+            first = false;
+        }
+        if self.elided {
+            fmt.write_str(if first { "…" } else { ".…" })?;
+            first = false;
+        }
+        for segment in self.segments() {
+            match segment {
+                PathSegment::Field(field) if first => fmt.write_str(field)?,
+                PathSegment::Field(field) => write!(fmt, ".{field}")?,
+                PathSegment::Index(index) => write!(fmt, "[{index}]")?,
+            }
+            first = false;
+        }
+        Ok(())
+    }
+}
+
+impl Error {
+    /// The absolute offset of the read this error occurred in, if it is known
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Error::At { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+
+    /// The error scroll reported, whether or not it has been located with [`Error::At`] since;
+    /// `None` for [`Error::Custom`] and [`Error::IO`].
+    ///
+    /// Errors returned from [`Pread`](trait.Pread.html) are located, so match on this instead of
+    /// the variants of `Error` to tell them apart:
+    ///
+    /// ```rust
+    /// use scroll::{Cause, Pread};
+    /// let err = [0u8; 2].pread::<u32>(0).unwrap_err();
+    /// assert!(matches!(err.kind(), Some(Cause::TooBig { size: 4, len: 2 })));
+    /// ```
+    pub fn kind(&self) -> Option<Cause> {
+        match *self {
+            Error::TooBig { size, len } => Some(Cause::TooBig { size, len }),
+            Error::BadOffset(offset) => Some(Cause::BadOffset(offset)),
+            Error::BadInput { size, msg } => Some(Cause::BadInput { size, msg }),
+            Error::BadTag { size, tag } => Some(Cause::BadTag { size, tag }),
+            Error::At { error, .. } => Some(error),
+            #[cfg(feature = "std")]
+            _ => None,
+        }
+    }
+
+    /// The path to the field of a derived type this error occurred in, if any
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Error::At { path, .. } if path.ty.is_some() || path.segments().next().is_some() => {
+                Some(path)
+            }
+            _ => None,
+        }
+    }
+
+    /// Record that this error occurred reading a value `offset` bytes into the buffer; offsets
+    /// already recorded are taken to be relative to that value, and are moved along with it.
+    ///
+    /// [`Pread::gread_with`](trait.Pread.html#method.gread_with) does this for every error on the
+    /// way out through [`Locate`], so the offset ends up relative to the outermost buffer.
+    pub fn at(self, offset: usize) -> Self {
+        self.locate(|at, _, error| {
+            *at += offset;
+            if let Cause::BadOffset(ref mut bad) = error {
+                *bad += offset;
+            }
+        })
+    }
+
+    /// Record that this error occurred in `field` of the derived type `ty`
+    pub fn in_field(self, ty: &'static str, field: &'static str) -> Self {
+        self.locate(|_, path, _| {
+            path.push_field(field);
+            path.ty = Some(ty);
+        })
+    }
+
+    /// Record that this error occurred reading the derived type `ty`, outside of its fields
+    pub fn in_type(self, ty: &'static str) -> Self {
+        self.locate(|_, path, _| path.ty = Some(ty))
+    }
+
+    /// Record that this error occurred in element `index` of a sequence
+    pub fn in_element(self, index: usize) -> Self {
+        self.locate(|_, path, _| path.push_index(index))
+    }
+
+    /// Turns the errors scroll reports into `At`, and updates it; other errors are passed through
+    fn locate(self, update: impl FnOnce(&mut usize, &mut Path, &mut Cause)) -> Self {
+        let (mut offset, mut path, mut error) = match self {
+            Error::At {
+                offset,
+                path,
+                error,
+            } => (offset, path, error),
+            Error::TooBig { size, len } => (0, Path::default(), Cause::TooBig { size, len }),
+            Error::BadOffset(offset) => (0, Path::default(), Cause::BadOffset(offset)),
+            Error::BadInput { size, msg } => (0, Path::default(), Cause::BadInput { size, msg }),
+            Error::BadTag { size, tag } => (0, Path::default(), Cause::BadTag { size, tag }),
+            #[cfg(feature = "std")]
+            error => return error,
+        };
+        update(&mut offset, &mut path, &mut error);
+        Error::At {
+            offset,
+            path,
+            error,
+        }
+    }
+}
+
+/// Error types which can record where in the input they occurred, like [`Error`] does with
+/// [`Error::At`].
+///
+/// scroll's readers hand every error of the values they read to these hooks on the way out, so
+/// that it ends up located in the outermost buffer. All of them do nothing by default, so an error
+/// type which doesn't keep track of locations only needs an empty impl:
+///
+/// ```rust
+/// #[derive(Debug)]
+/// pub struct MyError;
+///
+/// impl From<scroll::Error> for MyError {
+///     fn from(_: scroll::Error) -> Self {
+///         MyError
+///     }
+/// }
+///
+/// impl scroll::Locate for MyError {}
+/// ```
+pub trait Locate: Sized {
+    /// Record that this error occurred reading a value `offset` bytes into the buffer, see
+    /// [`Error::at`]
+    #[inline]
+    fn at(self, _offset: usize) -> Self {
+        self
+    }
+
+    /// Record that this error occurred in element `index` of a sequence, see
+    /// [`Error::in_element`]
+    #[inline]
+    fn in_element(self, _index: usize) -> Self {
+        self
+    }
+
+    /// The length the input needs to have at least for reading to get past this error, if it is
+    /// due to the input of length `len` ending too early.
+    ///
+    /// Readers which fetch their input on demand, like
+    /// [`IOread::ioread_try_with`](trait.IOread.html#method.ioread_try_with), use this to decide
+    /// how many more bytes to fetch; the default of `None` makes them give up instead.
+    #[inline]
+    fn needed_len(&self, _len: usize) -> Option<usize> {
+        None
+    }
+}
+
+/// The message of the `BadInput` error for a C string without its terminating null byte
+pub(crate) const NO_NULL_BYTE: &str = "The input doesn't contain a null byte";
+
+impl Locate for Error {
+    #[inline]
+    fn at(self, offset: usize) -> Self {
+        Error::at(self, offset)
+    }
+
+    #[inline]
+    fn in_element(self, index: usize) -> Self {
+        Error::in_element(self, index)
+    }
+
+    fn needed_len(&self, len: usize) -> Option<usize> {
+        let offset = self.offset().unwrap_or(0);
+        let needed = match self.kind()? {
+            Cause::TooBig { size, .. } => offset.checked_add(size)?,
+            Cause::BadOffset(offset) => offset.checked_add(1)?,
+            // the string may continue past the end of the input
+            Cause::BadInput { msg, .. } if msg == NO_NULL_BYTE => len.checked_add(1)?,
+            Cause::BadInput { .. } | Cause::BadTag { .. } => return None,
+        };
+        Some(needed).filter(|needed| *needed > len)
+    }
 }
 
 #[cfg(feature = "std")]
@@ -33,8 +366,10 @@ impl error::Error for Error {
             Error::TooBig { .. } => "TooBig",
             Error::BadOffset(_) => "BadOffset",
             Error::BadInput { .. } => "BadInput",
+            Error::BadTag { .. } => "BadTag",
             Error::Custom(_) => "Custom",
             Error::IO(_) => "IO",
+            Error::At { .. } => "At",
         }
     }
     fn cause(&self) -> Option<&dyn error::Error> {
@@ -42,8 +377,10 @@ impl error::Error for Error {
             Error::TooBig { .. } => None,
             Error::BadOffset(_) => None,
             Error::BadInput { .. } => None,
+            Error::BadTag { .. } => None,
             Error::Custom(_) => None,
             Error::IO(ref io) => io.source(),
+            Error::At { .. } => None,
         }
     }
 }
@@ -67,6 +404,9 @@ impl Display for Error {
             Error::BadInput { ref msg, ref size } => {
                 write!(fmt, "bad input {msg} ({size})")
             }
+            Error::BadTag { ref tag, ref size } => {
+                write!(fmt, "unknown tag {tag:#x} ({size})")
+            }
             #[cfg(feature = "std")]
             Error::Custom(ref msg) => {
                 write!(fmt, "{msg}")
@@ -75,8 +415,163 @@ impl Display for Error {
             Error::IO(ref err) => {
                 write!(fmt, "{err}")
             }
+            Error::At {
+                ref offset,
+                ref path,
+                ref error,
+            } => {
+                write!(fmt, "{error}")?;
+                // a bad offset already is the offset the error occurred at
+                if *error != Cause::BadOffset(*offset) {
+                    write!(fmt, " at offset {offset:#x}")?;
+                }
+                if self.path().is_some() {
+                    write!(fmt, " in {path}")?;
+                }
+                Ok(())
+            }
         }
     }
 }
 
 pub type Result<T> = result::Result<T, Error>;
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use super::*;
+    use crate::{ctx::TryFromCtx, Pread, LE};
+
+    /// A hand written nested type, so offsets accumulate through `gread_with` alone
+    #[derive(Debug)]
+    struct Pair;
+
+    impl<'a> TryFromCtx<'a, ()> for Pair {
+        type Error = Error;
+        fn try_from_ctx(src: &'a [u8], _: ()) -> Result<(Self, usize)> {
+            let offset = &mut 0;
+            src.gread_with::<u16>(offset, LE)?;
+            src.gread_with::<u32>(offset, LE)?;
+            Ok((Pair, *offset))
+        }
+    }
+
+    #[test]
+    fn offsets_are_absolute() {
+        let bytes = [0u8; 8];
+        let err = bytes.pread_with::<Pair>(3, ()).unwrap_err();
+        assert_eq!(err.offset(), Some(5));
+        assert!(err.path().is_none());
+        assert_eq!(err.to_string(), "type is too big (4) for 3 at offset 0x5");
+
+        let err = bytes.pread::<u8>(9).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::At {
+                offset: 9,
+                error: Cause::BadOffset(9),
+                ..
+            }
+        ));
+        assert_eq!(err.to_string(), "bad offset 9");
+    }
+
+    /// An error borrowing from the buffer read from
+    #[derive(Debug)]
+    struct Mismatch<'a>(&'a [u8]);
+
+    impl From<Error> for Mismatch<'_> {
+        fn from(_: Error) -> Self {
+            Mismatch(&[])
+        }
+    }
+
+    impl Locate for Mismatch<'_> {}
+
+    #[derive(Debug)]
+    struct Magic;
+
+    impl<'a> TryFromCtx<'a, ()> for Magic {
+        type Error = Mismatch<'a>;
+        fn try_from_ctx(src: &'a [u8], _: ()) -> result::Result<(Self, usize), Mismatch<'a>> {
+            match src.get(..2) {
+                Some(b"MZ") => Ok((Magic, 2)),
+                _ => Err(Mismatch(src)),
+            }
+        }
+    }
+
+    #[test]
+    fn foreign_errors_pass_through() {
+        let bytes = *b"\0MZZM";
+        assert!(bytes.pread::<Magic>(1).is_ok());
+        let err = bytes.pread::<Magic>(3).unwrap_err();
+        assert_eq!(err.0, b"ZM");
+    }
+
+    #[test]
+    fn kind_sees_through_locations() {
+        let err = [0u8; 4].pread_with::<u64>(1, LE).unwrap_err();
+        assert_eq!(err.kind(), Some(Cause::TooBig { size: 8, len: 3 }));
+        assert_eq!(err.offset(), Some(1));
+        assert!(Error::Custom("custom".into()).kind().is_none());
+    }
+
+    #[test]
+    fn paths_elide_outer_steps() {
+        let err = Error::BadInput {
+            size: 1,
+            msg: "bad",
+        }
+        .in_field("Inner", "a")
+        .in_element(1)
+        .in_field("Middle", "b")
+        .in_element(2)
+        .in_field("Outer", "c")
+        .in_element(3)
+        .in_field("Outermost", "d")
+        .at(0x10);
+        let path = err.path().unwrap();
+        assert_eq!(path.to_string(), "Outermost.….c[2].b[1].a");
+        assert_eq!(path.type_name(), Some("Outermost"));
+        assert_eq!(
+            path.segments().collect::<Vec<_>>(),
+            [
+                PathSegment::Field("c"),
+                PathSegment::Index(2),
+                PathSegment::Field("b"),
+                PathSegment::Index(1),
+                PathSegment::Field("a")
+            ]
+        );
+        assert_eq!(
+            err.to_string(),
+            "bad input bad (1) at offset 0x10 in Outermost.….c[2].b[1].a"
+        );
+
+        let err = Error::Custom("custom".into()).in_field("Outer", "c").at(1);
+        assert!(err.offset().is_none());
+    }
+
+    #[test]
+    fn paths_keep_indices_outside_of_fields() {
+        let err = Error::BadOffset(0).in_element(4).at(0x10);
+        let path = err.path().unwrap();
+        assert_eq!(path.type_name(), None);
+        assert_eq!(path.segments().collect::<Vec<_>>(), [PathSegment::Index(4)]);
+        assert_eq!(err.to_string(), "bad offset 16 in [4]");
+
+        let err = Error::TooBig { size: 2, len: 1 }
+            .in_field("Inner", "a")
+            .in_element(3);
+        assert_eq!(err.path().unwrap().to_string(), "Inner[3].a");
+
+        let bytes = [1u8, 0, 2, 0, 3];
+        let err = bytes.pread_with::<Vec<u16>>(0, (3, LE)).unwrap_err();
+        assert_eq!(err.path().unwrap().to_string(), "[2]");
+        assert_eq!(
+            err.to_string(),
+            "type is too big (2) for 1 at offset 0x4 in [2]"
+        );
+    }
+}
@@ -1,78 +1,8 @@
 use core::convert::{AsRef, From};
-use core::{result, u8};
+use core::result;
 
-use crate::ctx::TryFromCtx;
-use crate::{error, Pread};
-
-#[derive(Debug, PartialEq, Copy, Clone)]
-/// An unsigned leb128 integer
-pub struct Uleb128 {
-    value: u64,
-    count: usize,
-}
-
-impl Uleb128 {
-    #[inline]
-    /// Return how many bytes this Uleb128 takes up in memory
-    pub fn size(&self) -> usize {
-        self.count
-    }
-    #[inline]
-    /// Read a variable length u64 from `bytes` at `offset`
-    pub fn read(bytes: &[u8], offset: &mut usize) -> error::Result<u64> {
-        let tmp = bytes.pread::<Uleb128>(*offset)?;
-        *offset += tmp.size();
-        Ok(tmp.into())
-    }
-}
-
-impl AsRef<u64> for Uleb128 {
-    fn as_ref(&self) -> &u64 {
-        &self.value
-    }
-}
-
-impl From<Uleb128> for u64 {
-    #[inline]
-    fn from(uleb128: Uleb128) -> u64 {
-        uleb128.value
-    }
-}
-
-#[derive(Debug, PartialEq, Copy, Clone)]
-/// An signed leb128 integer
-pub struct Sleb128 {
-    value: i64,
-    count: usize,
-}
-
-impl Sleb128 {
-    #[inline]
-    /// Return how many bytes this Sleb128 takes up in memory
-    pub fn size(&self) -> usize {
-        self.count
-    }
-    #[inline]
-    /// Read a variable length i64 from `bytes` at `offset`
-    pub fn read(bytes: &[u8], offset: &mut usize) -> error::Result<i64> {
-        let tmp = bytes.pread::<Sleb128>(*offset)?;
-        *offset += tmp.size();
-        Ok(tmp.into())
-    }
-}
-
-impl AsRef<i64> for Sleb128 {
-    fn as_ref(&self) -> &i64 {
-        &self.value
-    }
-}
-
-impl From<Sleb128> for i64 {
-    #[inline]
-    fn from(sleb128: Sleb128) -> i64 {
-        sleb128.value
-    }
-}
+use crate::ctx::{MeasureWith, TryFromCtx, TryIntoCtx};
+use crate::{error, Pread, Pwrite};
 
 // Below implementation heavily adapted from: https://github.com/fitzgen/leb128
 const CONTINUATION_BIT: u8 = 1 << 7;
@@ -89,90 +19,266 @@ fn mask_continuation(byte: u8) -> u8 {
 //     mask_continuation(byte as u8)
 // }
 
-impl<'a> TryFromCtx<'a> for Uleb128 {
-    type Error = error::Error;
-    #[inline]
-    fn try_from_ctx(src: &'a [u8], _ctx: ()) -> result::Result<(Self, usize), Self::Error> {
-        let mut result = 0;
-        let mut shift = 0;
-        let mut count = 0;
-        loop {
-            let byte: u8 = src.pread(count)?;
-
-            if shift == 63 && byte != 0x00 && byte != 0x01 {
-                return Err(error::Error::BadInput {
-                    size: src.len(),
-                    msg: "failed to parse",
-                });
+/// The struct, accessors and conversions shared by every leb128 integer type
+macro_rules! leb128_common_impl {
+    ($(#[$attr:meta])* $name:ident, $typ:ty) => {
+        #[derive(Debug, PartialEq, Copy, Clone)]
+        $(#[$attr])*
+        pub struct $name {
+            value: $typ,
+            count: usize,
+        }
+
+        impl $name {
+            #[inline]
+            #[doc = concat!("Return how many bytes this ", stringify!($name), " takes up in memory, i.e. the length of its encoding")]
+            pub fn size(&self) -> usize {
+                self.count
+            }
+            #[inline]
+            #[doc = concat!("Read a variable length ", stringify!($typ), " from `bytes` at `offset`")]
+            pub fn read(bytes: &[u8], offset: &mut usize) -> error::Result<$typ> {
+                let tmp = bytes.pread::<$name>(*offset)?;
+                *offset += tmp.size();
+                Ok(tmp.into())
+            }
+        }
+
+        impl AsRef<$typ> for $name {
+            fn as_ref(&self) -> &$typ {
+                &self.value
             }
+        }
 
-            let low_bits = u64::from(mask_continuation(byte));
-            result |= low_bits << shift;
+        /// The length of its encoding, as with [`size`](#method.size)
+        impl MeasureWith<()> for $name {
+            #[inline]
+            fn measure_with(&self, _ctx: &()) -> usize {
+                self.count
+            }
+        }
 
-            count += 1;
-            shift += 7;
+        impl From<$name> for $typ {
+            #[inline]
+            fn from(leb128: $name) -> $typ {
+                leb128.value
+            }
+        }
 
-            if byte & CONTINUATION_BIT == 0 {
-                return Ok((
-                    Uleb128 {
-                        value: result,
-                        count,
-                    },
-                    count,
-                ));
+        /// Write the low bits of the value as `count` bytes, each but the last having its
+        /// continuation bit set; signed values are shifted arithmetically, which sign extends the
+        /// padding.
+        impl TryIntoCtx for $name {
+            type Error = error::Error;
+            #[inline]
+            fn try_into_ctx(self, dst: &mut [u8], _ctx: ()) -> result::Result<usize, Self::Error> {
+                let mut value = self.value;
+                let offset = &mut 0;
+                for i in 1..=self.count {
+                    let byte = mask_continuation(value as u8);
+                    value >>= 7;
+                    if i == self.count {
+                        dst.gwrite(byte, offset)?;
+                    } else {
+                        dst.gwrite(byte | CONTINUATION_BIT, offset)?;
+                    }
+                }
+                Ok(*offset)
             }
         }
-    }
+
+        impl TryIntoCtx for &$name {
+            type Error = error::Error;
+            #[inline]
+            fn try_into_ctx(self, dst: &mut [u8], ctx: ()) -> result::Result<usize, Self::Error> {
+                (*self).try_into_ctx(dst, ctx)
+            }
+        }
+    };
 }
 
-impl<'a> TryFromCtx<'a> for Sleb128 {
-    type Error = error::Error;
-    #[inline]
-    fn try_from_ctx(src: &'a [u8], _ctx: ()) -> result::Result<(Self, usize), Self::Error> {
-        let o = 0;
-        let offset = &mut 0;
-        let mut result = 0;
-        let mut shift = 0;
-        let size = 64;
-        let mut byte: u8;
-        loop {
-            byte = src.gread(offset)?;
-
-            if shift == 63 && byte != 0x00 && byte != 0x7f {
-                return Err(error::Error::BadInput {
-                    size: src.len(),
-                    msg: "failed to parse",
-                });
+/// An unsigned leb128 integer decoding to `$typ`; encodings longer than needed to hold any
+/// `$typ`, or holding a value not fitting into one, are rejected
+macro_rules! uleb128_impl {
+    ($(#[$attr:meta])* $name:ident, $typ:ty) => {
+        leb128_common_impl!($(#[$attr])* $name, $typ);
+
+        impl From<$typ> for $name {
+            #[doc = concat!("Create a ", stringify!($name), " for `value`, using the shortest encoding")]
+            #[inline]
+            fn from(value: $typ) -> $name {
+                let mut count = 1;
+                let mut rest = value >> 7;
+                while rest != 0 {
+                    rest >>= 7;
+                    count += 1;
+                }
+                $name { value, count }
             }
+        }
+
+        impl<'a> TryFromCtx<'a> for $name {
+            type Error = error::Error;
+            #[inline]
+            fn try_from_ctx(src: &'a [u8], _ctx: ()) -> result::Result<(Self, usize), Self::Error> {
+                let bits = <$typ>::BITS;
+                let mut result: $typ = 0;
+                let mut shift = 0;
+                let mut count = 0;
+                loop {
+                    let byte: u8 = src.pread(count)?;
+                    let low_bits = mask_continuation(byte);
+
+                    if shift + 7 >= bits {
+                        // the last byte an encoding of this size may have
+                        if byte & CONTINUATION_BIT != 0 {
+                            return Err(error::Error::BadInput {
+                                size: src.len(),
+                                msg: concat!(stringify!($name), " encoding is too long"),
+                            });
+                        }
+                        if low_bits >> (bits - shift) != 0 {
+                            return Err(error::Error::BadInput {
+                                size: src.len(),
+                                msg: concat!(stringify!($name), " value does not fit into ", stringify!($typ)),
+                            });
+                        }
+                    }
 
-            let low_bits = i64::from(mask_continuation(byte));
-            result |= low_bits << shift;
-            shift += 7;
+                    result |= <$typ>::from(low_bits) << shift;
 
-            if byte & CONTINUATION_BIT == 0 {
-                break;
+                    count += 1;
+                    shift += 7;
+
+                    if byte & CONTINUATION_BIT == 0 {
+                        return Ok((
+                            $name {
+                                value: result,
+                                count,
+                            },
+                            count,
+                        ));
+                    }
+                }
             }
         }
+    };
+}
+
+/// A signed leb128 integer decoding to `$typ`; encodings longer than needed to hold any `$typ`, or
+/// holding a value not fitting into one, are rejected
+macro_rules! sleb128_impl {
+    ($(#[$attr:meta])* $name:ident, $typ:ty) => {
+        leb128_common_impl!($(#[$attr])* $name, $typ);
 
-        if shift < size && (SIGN_BIT & byte) == SIGN_BIT {
-            // Sign extend the result.
-            result |= !0 << shift;
+        impl From<$typ> for $name {
+            #[doc = concat!("Create a ", stringify!($name), " for `value`, using the shortest encoding")]
+            #[inline]
+            fn from(value: $typ) -> $name {
+                let mut count = 1;
+                let mut rest = value;
+                // the last byte must carry the sign of `value` in its `SIGN_BIT`
+                while !(-64..64).contains(&rest) {
+                    rest >>= 7;
+                    count += 1;
+                }
+                $name { value, count }
+            }
         }
-        let count = *offset - o;
-        Ok((
-            Sleb128 {
-                value: result,
-                count,
-            },
-            count,
-        ))
-    }
+
+        impl<'a> TryFromCtx<'a> for $name {
+            type Error = error::Error;
+            #[inline]
+            fn try_from_ctx(src: &'a [u8], _ctx: ()) -> result::Result<(Self, usize), Self::Error> {
+                let bits = <$typ>::BITS;
+                let mut result: $typ = 0;
+                let mut shift = 0;
+                let mut count = 0;
+                let mut byte: u8;
+                loop {
+                    byte = src.pread(count)?;
+                    let low_bits = mask_continuation(byte);
+
+                    if shift + 7 >= bits {
+                        // the last byte an encoding of this size may have
+                        if byte & CONTINUATION_BIT != 0 {
+                            return Err(error::Error::BadInput {
+                                size: src.len(),
+                                msg: concat!(stringify!($name), " encoding is too long"),
+                            });
+                        }
+                        // the bits beyond the width of the value must all equal its sign
+                        let sign_extended = ((low_bits << 1) as i8) >> (bits - shift);
+                        if sign_extended != 0 && sign_extended != -1 {
+                            return Err(error::Error::BadInput {
+                                size: src.len(),
+                                msg: concat!(stringify!($name), " value does not fit into ", stringify!($typ)),
+                            });
+                        }
+                    }
+
+                    result |= <$typ>::from(low_bits) << shift;
+
+                    count += 1;
+                    shift += 7;
+
+                    if byte & CONTINUATION_BIT == 0 {
+                        break;
+                    }
+                }
+
+                if shift < bits && (SIGN_BIT & byte) == SIGN_BIT {
+                    // Sign extend the result.
+                    result |= !0 << shift;
+                }
+                Ok((
+                    $name {
+                        value: result,
+                        count,
+                    },
+                    count,
+                ))
+            }
+        }
+    };
 }
 
+uleb128_impl!(
+    /// An unsigned leb128 integer
+    Uleb128,
+    u64
+);
+sleb128_impl!(
+    /// An signed leb128 integer
+    Sleb128,
+    i64
+);
+uleb128_impl!(
+    /// An unsigned leb128 integer of at most 32 bits, e.g. WebAssembly's `varuint32`
+    Uleb32,
+    u32
+);
+sleb128_impl!(
+    /// A signed leb128 integer of at most 32 bits, e.g. WebAssembly's `varint32`
+    Sleb32,
+    i32
+);
+uleb128_impl!(
+    /// An unsigned leb128 integer of at most 128 bits
+    Uleb128_128,
+    u128
+);
+sleb128_impl!(
+    /// A signed leb128 integer of at most 128 bits
+    Sleb128_128,
+    i128
+);
+
 #[cfg(test)]
 mod tests {
     use super::super::LE;
-    use super::{Sleb128, Uleb128};
+    use super::{Sleb128, Sleb128_128, Sleb32, Uleb128, Uleb128_128, Uleb32};
+    use crate::error::{Cause, Error};
 
     const CONTINUATION_BIT: u8 = 1 << 7;
     //const SIGN_BIT: u8 = 1 << 6;
@@ -248,4 +354,174 @@ mod tests {
             .into();
         assert_eq!(-129, num);
     }
+
+    #[test]
+    fn uleb128_roundtrip() {
+        use super::super::{Pread, Pwrite};
+        let values = [
+            0,
+            1,
+            63,
+            64,
+            127,
+            128,
+            129,
+            16383,
+            16384,
+            u32::MAX as u64,
+            u64::MAX,
+        ];
+        for value in values {
+            let num = Uleb128::from(value);
+            let mut buf = [0xffu8; 10];
+            let written = buf.pwrite(num, 0).expect("Should write Uleb128");
+            assert_eq!(written, num.size());
+            let read = buf.pread::<Uleb128>(0).expect("Should read Uleb128");
+            assert_eq!(read, num);
+            assert_eq!(u64::from(read), value);
+        }
+        assert_eq!(Uleb128::from(127).size(), 1);
+        assert_eq!(Uleb128::from(128).size(), 2);
+        assert_eq!(Uleb128::from(u64::MAX).size(), 10);
+    }
+
+    #[test]
+    fn sleb128_roundtrip() {
+        use super::super::{Pread, Pwrite};
+        let values = [0, 1, -1, 63, 64, -64, -65, 8191, -8192, i64::MAX, i64::MIN];
+        for value in values {
+            let num = Sleb128::from(value);
+            let mut buf = [0xffu8; 10];
+            let written = buf.pwrite(num, 0).expect("Should write Sleb128");
+            assert_eq!(written, num.size());
+            let read = buf.pread::<Sleb128>(0).expect("Should read Sleb128");
+            assert_eq!(read, num);
+            assert_eq!(i64::from(read), value);
+        }
+        assert_eq!(Sleb128::from(63).size(), 1);
+        assert_eq!(Sleb128::from(64).size(), 2);
+        assert_eq!(Sleb128::from(-64).size(), 1);
+        assert_eq!(Sleb128::from(-65).size(), 2);
+        assert_eq!(Sleb128::from(i64::MIN).size(), 10);
+    }
+
+    #[test]
+    fn leb128_gwrite_keeps_padding() {
+        use super::super::{Pread, Pwrite};
+        // non-minimal encodings of 2 and -1 are written back as they were read
+        let bytes = [0x82u8, 0x80, 0x00, 0xff, 0x7f];
+        let offset = &mut 0;
+        let uleb: Uleb128 = bytes.gread(offset).unwrap();
+        let sleb: Sleb128 = bytes.gread(offset).unwrap();
+        assert_eq!(u64::from(uleb), 2);
+        assert_eq!(i64::from(sleb), -1);
+
+        let mut buf = [0u8; 5];
+        let offset = &mut 0;
+        buf.gwrite(&uleb, offset).unwrap();
+        buf.gwrite(sleb, offset).unwrap();
+        assert_eq!(*offset, 5);
+        assert_eq!(buf, bytes);
+
+        let mut short = [0u8; 2];
+        assert!(short.pwrite(uleb, 0).is_err());
+    }
+
+    fn bad_input_msg<T: core::fmt::Debug>(result: Result<T, Error>) -> &'static str {
+        match result {
+            Err(Error::At {
+                error: Cause::BadInput { msg, .. },
+                ..
+            }) => msg,
+            other => panic!("expected BadInput, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn leb32() {
+        use super::super::Pread;
+        let bytes = [0xffu8, 0xff, 0xff, 0xff, 0x0f];
+        assert_eq!(u32::from(bytes.pread::<Uleb32>(0).unwrap()), u32::MAX);
+        let bytes = [0xffu8, 0xff, 0xff, 0xff, 0x1f];
+        assert_eq!(
+            bad_input_msg(bytes.pread::<Uleb32>(0)),
+            "Uleb32 value does not fit into u32"
+        );
+        let bytes = [0x80u8, 0x80, 0x80, 0x80, 0x80, 0x00];
+        assert_eq!(
+            bad_input_msg(bytes.pread::<Uleb32>(0)),
+            "Uleb32 encoding is too long"
+        );
+        // padded encodings are fine as long as they fit
+        let bytes = [0x80u8, 0x80, 0x80, 0x80, 0x00];
+        assert_eq!(u32::from(bytes.pread::<Uleb32>(0).unwrap()), 0);
+
+        let bytes = [0x80u8, 0x80, 0x80, 0x80, 0x78];
+        assert_eq!(i32::from(bytes.pread::<Sleb32>(0).unwrap()), i32::MIN);
+        let bytes = [0xffu8, 0xff, 0xff, 0xff, 0x07];
+        assert_eq!(i32::from(bytes.pread::<Sleb32>(0).unwrap()), i32::MAX);
+        let bytes = [0xffu8, 0xff, 0xff, 0xff, 0x0f];
+        assert_eq!(
+            bad_input_msg(bytes.pread::<Sleb32>(0)),
+            "Sleb32 value does not fit into i32"
+        );
+        let bytes = [0x80u8, 0x80, 0x80, 0x80, 0x70];
+        assert_eq!(
+            bad_input_msg(bytes.pread::<Sleb32>(0)),
+            "Sleb32 value does not fit into i32"
+        );
+        let bytes = [0xffu8, 0xff, 0xff, 0xff, 0xff, 0x7f];
+        assert_eq!(
+            bad_input_msg(bytes.pread::<Sleb32>(0)),
+            "Sleb32 encoding is too long"
+        );
+    }
+
+    #[test]
+    fn leb64_limits() {
+        use super::super::Pread;
+        let mut bytes = [0xffu8; 10];
+        bytes[9] = 0x01;
+        assert_eq!(u64::from(bytes.pread::<Uleb128>(0).unwrap()), u64::MAX);
+        bytes[9] = 0x02;
+        assert_eq!(
+            bad_input_msg(bytes.pread::<Uleb128>(0)),
+            "Uleb128 value does not fit into u64"
+        );
+        bytes[9] = 0x7f;
+        assert_eq!(i64::from(bytes.pread::<Sleb128>(0).unwrap()), -1);
+        bytes[9] = 0x3f;
+        assert_eq!(
+            bad_input_msg(bytes.pread::<Sleb128>(0)),
+            "Sleb128 value does not fit into i64"
+        );
+    }
+
+    #[test]
+    fn leb_roundtrip_widths() {
+        use super::super::{Pread, Pwrite};
+        let mut buf = [0u8; 19];
+        for value in [0, 1, 127, 128, u32::MAX] {
+            let written = buf.pwrite(Uleb32::from(value), 0).unwrap();
+            let read = buf.pread::<Uleb32>(0).unwrap();
+            assert_eq!((u32::from(read), read.size()), (value, written));
+        }
+        for value in [0, -1, 63, -64, 64, -65, i32::MIN, i32::MAX] {
+            let written = buf.pwrite(Sleb32::from(value), 0).unwrap();
+            let read = buf.pread::<Sleb32>(0).unwrap();
+            assert_eq!((i32::from(read), read.size()), (value, written));
+        }
+        for value in [0, 1, u64::MAX as u128 + 1, u128::MAX] {
+            let written = buf.pwrite(Uleb128_128::from(value), 0).unwrap();
+            let read = buf.pread::<Uleb128_128>(0).unwrap();
+            assert_eq!((u128::from(read), read.size()), (value, written));
+        }
+        for value in [0, -1, i64::MIN as i128 - 1, i128::MIN, i128::MAX] {
+            let written = buf.pwrite(Sleb128_128::from(value), 0).unwrap();
+            let read = buf.pread::<Sleb128_128>(0).unwrap();
+            assert_eq!((i128::from(read), read.size()), (value, written));
+        }
+        assert_eq!(Uleb128_128::from(u128::MAX).size(), 19);
+        assert_eq!(Sleb128_128::from(i128::MIN).size(), 19);
+    }
 }
@@ -1,6 +1,47 @@
-use std::io::{Read, Result, Write};
+use std::io::{Error, ErrorKind, Read, Result, Write};
 
-use crate::ctx::{FromCtx, IntoCtx, SizeWith};
+use crate::ctx::{FromCtx, IntoCtx, SizeWith, TryFromCtx, TryIntoCtx};
+use crate::error;
+use crate::GrowableBuffer;
+
+/// The size up to which values are read and written through a scratch buffer on the stack; larger
+/// ones go through the heap.
+const STACK_SCRATCH: usize = 256;
+
+/// A zeroed buffer values are read and written through
+// keeping small buffers inline is the point
+#[allow(clippy::large_enum_variant)]
+pub(crate) enum Scratch {
+    Stack([u8; STACK_SCRATCH], usize),
+    Heap(Vec<u8>),
+}
+
+impl Scratch {
+    /// A buffer of `size` bytes, failing if it can not be allocated
+    #[inline]
+    pub(crate) fn new(size: usize) -> Result<Self> {
+        if size <= STACK_SCRATCH {
+            return Ok(Scratch::Stack([0; STACK_SCRATCH], size));
+        }
+        let mut scratch = Vec::new();
+        scratch.try_reserve_exact(size).map_err(|_| {
+            Error::new(
+                ErrorKind::OutOfMemory,
+                "can not allocate a scratch buffer of the size of the type",
+            )
+        })?;
+        scratch.resize(size, 0);
+        Ok(Scratch::Heap(scratch))
+    }
+
+    #[inline]
+    pub(crate) fn as_mut(&mut self) -> &mut [u8] {
+        match self {
+            Scratch::Stack(scratch, size) => &mut scratch[..*size],
+            Scratch::Heap(scratch) => scratch,
+        }
+    }
+}
 
 /// An extension trait to `std::io::Read` streams; mainly targeted at reading primitive types with
 /// a known size.
@@ -12,7 +53,8 @@ use crate::ctx::{FromCtx, IntoCtx, SizeWith};
 /// buffer (the size you specified in `SizeWith`), or out of bound errors (depending on your impl)
 /// in `from_ctx`.
 ///
-/// Warning: Currently ioread/write uses a small 256-byte buffer and can not read/write larger types
+/// Types of up to 256 bytes are read through a buffer on the stack, larger ones through one on the
+/// heap.
 ///
 /// # Example
 /// ```rust
@@ -82,7 +124,7 @@ pub trait IOread<Ctx: Copy>: Read {
     }
 
     /// Reads the type `N` from `Self`, with the parsing context `ctx`.
-    /// **NB**: this will panic if the type you're reading has a size greater than 256. Plans are to have this allocate in larger cases.
+    /// Returns an `OutOfMemory` error if the type is too big to allocate a buffer for.
     ///
     /// For the primitive numeric types, this will be at the host machine's endianness.
     ///
@@ -103,12 +145,59 @@ pub trait IOread<Ctx: Copy>: Read {
     /// ```
     #[inline]
     fn ioread_with<N: FromCtx<Ctx> + SizeWith<Ctx>>(&mut self, ctx: Ctx) -> Result<N> {
-        let mut scratch = [0u8; 256];
-        let size = N::size_with(&ctx);
-        let buf = &mut scratch[0..size];
+        let mut scratch = Scratch::new(N::size_with(&ctx))?;
+        let buf = scratch.as_mut();
         self.read_exact(buf)?;
         Ok(N::from_ctx(buf, ctx))
     }
+
+    /// Reads the type `N` from `Self` with the parsing context `ctx`, for types whose parsing can
+    /// fail or whose size depends on their contents, like [`Uleb128`](struct.Uleb128.html) or
+    /// `CString`.
+    ///
+    /// Bytes are read as `N` asks for them by failing with `TooBig` or `BadOffset` errors past the
+    /// end of what was read so far, or for a C string missing its null byte, so no more bytes than
+    /// the value takes up are consumed. Other parsing errors are returned as is, and IO errors,
+    /// like the stream ending, as [`Error::IO`](enum.Error.html#variant.IO).
+    ///
+    /// `N` is parsed again from the start whenever more bytes have been read. Values which only
+    /// ever ask for one more byte, like `CString` or a delimited `&str`, are thus read one byte
+    /// per `read` call and parsed once per byte, which takes time quadratic in their length; wrap
+    /// unbuffered streams in a [`BufReader`](https://doc.rust-lang.org/std/io/struct.BufReader.html),
+    /// and read long values of this kind from a buffer instead.
+    ///
+    /// # Example
+    /// ```rust
+    /// use scroll::{IOread, Uleb128, LE};
+    /// use std::ffi::CString;
+    /// use std::io::Cursor;
+    /// let mut bytes = Cursor::new([0xe5, 0x8e, 0x26, b'h', b'i', 0x00, 0x2a]);
+    /// let uleb = bytes.ioread_try_with::<Uleb128>(()).unwrap();
+    /// assert_eq!(u64::from(uleb), 624485);
+    /// let hi = bytes.ioread_try_with::<CString>(()).unwrap();
+    /// assert_eq!(hi.as_bytes(), b"hi");
+    /// assert_eq!(bytes.ioread_with::<u8>(LE).unwrap(), 0x2a);
+    /// assert!(bytes.ioread_try_with::<Uleb128>(()).is_err());
+    /// ```
+    fn ioread_try_with<N>(&mut self, ctx: Ctx) -> error::Result<N>
+    where
+        N: for<'a> TryFromCtx<'a, Ctx, Error = error::Error>,
+    {
+        let mut buf = Vec::new();
+        loop {
+            let needed = match N::try_from_ctx(&buf, ctx) {
+                Ok((n, size)) if size <= buf.len() => return Ok(n),
+                Ok((_, size)) => size,
+                Err(err) => error::Locate::needed_len(&err, buf.len()).ok_or(err)?,
+            };
+            let missing = needed - buf.len();
+            // grows `buf` only as data arrives, however much `N` claims to need
+            let read = self.take(missing as u64).read_to_end(&mut buf)?;
+            if read < missing {
+                return Err(Error::from(ErrorKind::UnexpectedEof).into());
+            }
+        }
+    }
 }
 
 /// Types that implement `Read` get methods defined in `IOread`
@@ -120,7 +209,7 @@ impl<Ctx: Copy, R: Read + ?Sized> IOread<Ctx> for R {}
 /// To write custom types with a single `iowrite::<YourType>` call, implement [`IntoCtx`](ctx/trait.IntoCtx.html) and [`SizeWith`](ctx/trait.SizeWith.html) for `YourType`.
 pub trait IOwrite<Ctx: Copy>: Write {
     /// Writes the type `N` into `Self`, with the parsing context `ctx`.
-    /// **NB**: this will panic if the type you're writing has a size greater than 256. Plans are to have this allocate in larger cases.
+    /// Returns an `OutOfMemory` error if the type is too big to allocate a buffer for.
     ///
     /// For the primitive numeric types, this will be at the host machine's endianness.
     ///
@@ -148,7 +237,7 @@ pub trait IOwrite<Ctx: Copy>: Write {
     }
 
     /// Writes the type `N` into `Self`, with the parsing context `ctx`.
-    /// **NB**: this will panic if the type you're writing has a size greater than 256. Plans are to have this allocate in larger cases.
+    /// Returns an `OutOfMemory` error if the type is too big to allocate a buffer for.
     ///
     /// For the primitive numeric types, this will be at the host machine's endianness.
     ///
@@ -165,12 +254,36 @@ pub trait IOwrite<Ctx: Copy>: Write {
     /// ```
     #[inline]
     fn iowrite_with<N: SizeWith<Ctx> + IntoCtx<Ctx>>(&mut self, n: N, ctx: Ctx) -> Result<()> {
-        let mut buf = [0u8; 256];
-        let size = N::size_with(&ctx);
-        let buf = &mut buf[0..size];
+        let mut scratch = Scratch::new(N::size_with(&ctx))?;
+        let buf = scratch.as_mut();
         n.into_ctx(buf, ctx);
-        self.write_all(buf)?;
-        Ok(())
+        self.write_all(buf)
+    }
+
+    /// Writes the type `N` into `Self` with the parsing context `ctx`, for types whose writing can
+    /// fail or whose size depends on their contents.
+    ///
+    /// `N` is written into a [`GrowableBuffer`](struct.GrowableBuffer.html) first, and only written
+    /// to the stream if that succeeds; IO errors are returned as
+    /// [`Error::IO`](enum.Error.html#variant.IO).
+    ///
+    /// # Example
+    /// ```rust
+    /// use scroll::{IOwrite, Uleb128};
+    /// use std::io::Cursor;
+    /// let mut cursor = Cursor::new(Vec::new());
+    /// cursor.iowrite_try_with(Uleb128::from(624485), ()).unwrap();
+    /// cursor.iowrite_try_with("hi", ()).unwrap();
+    /// assert_eq!(cursor.into_inner(), [0xe5, 0x8e, 0x26, b'h', b'i']);
+    /// ```
+    fn iowrite_try_with<N>(&mut self, n: N, ctx: Ctx) -> error::Result<usize>
+    where
+        N: TryIntoCtx<Ctx, Error = error::Error> + Clone,
+    {
+        let mut buf = GrowableBuffer::new();
+        let size = buf.pwrite_with(n, 0, ctx)?;
+        self.write_all(&buf[..size])?;
+        Ok(size)
     }
 }
 
@@ -34,9 +34,9 @@
 //!
 //!
 //! In most cases you can use [scroll_derive](https://docs.rs/scroll_derive) to derive sensible
-//! defaults for `Pread`, `Pwrite`, their IO counterpart and `SizeWith`.  More complex situations
-//! call for manual implementation of those traits; refer to [the ctx module](ctx/index.html) for
-//! details.
+//! defaults for `Pread`, `Pwrite`, their IO counterpart, `SizeWith` and `MeasureWith`.  More
+//! complex situations call for manual implementation of those traits; refer to [the ctx
+//! module](ctx/index.html) for details.
 //!
 //!
 //! ## Efficiently:
@@ -227,33 +227,55 @@
 
 #[cfg(feature = "derive")]
 #[allow(unused_imports)]
-pub use scroll_derive::{IOread, IOwrite, Pread, Pwrite, SizeWith};
+pub use scroll_derive::{IOread, IOwrite, MeasureWith, Pread, Pwrite, SizeWith};
 
 #[cfg(feature = "std")]
 extern crate core;
 
+#[cfg(feature = "tokio")]
+mod async_io;
+mod bits;
+#[cfg(feature = "std")]
+mod chunks;
 pub mod ctx;
+mod cursor;
 mod endian;
 mod error;
 mod greater;
 mod leb128;
 #[cfg(feature = "std")]
 mod lesser;
+#[cfg(feature = "mmap")]
+mod mmap;
 mod pread;
 mod pwrite;
+#[cfg(feature = "std")]
+mod seek;
+mod table;
 
+#[cfg(feature = "tokio")]
+pub use crate::async_io::*;
+pub use crate::bits::*;
+#[cfg(feature = "std")]
+pub use crate::chunks::*;
+pub use crate::cursor::*;
 pub use crate::endian::*;
 pub use crate::error::*;
 pub use crate::greater::*;
 pub use crate::leb128::*;
 #[cfg(feature = "std")]
 pub use crate::lesser::*;
+#[cfg(feature = "mmap")]
+pub use crate::mmap::*;
 pub use crate::pread::*;
 pub use crate::pwrite::*;
+#[cfg(feature = "std")]
+pub use crate::seek::*;
+pub use crate::table::*;
 
 #[doc(hidden)]
 pub mod export {
-    pub use ::core::{mem, result};
+    pub use ::core::{cmp, convert, mem, result};
 }
 
 #[allow(unused)]
@@ -425,6 +447,7 @@ mod tests {
     use core::fmt::{self, Display};
 
     #[derive(Debug)]
+    #[allow(dead_code)]
     pub struct ExternalError {}
 
     impl Display for ExternalError {
@@ -450,6 +473,7 @@ mod tests {
     }
 
     #[derive(Debug, PartialEq, Eq)]
+    #[allow(dead_code)]
     pub struct Foo(u16);
 
     impl super::ctx::TryIntoCtx<super::Endian> for Foo {
@@ -597,6 +621,42 @@ mod tests {
         assert_eq!(*offset, bytes_to.len());
     }
 
+    #[cfg(feature = "std")]
+    #[test]
+    fn gread_iter() {
+        use super::ctx::StrCtx;
+        use super::{Error, Pread, BE};
+        let bytes = b"ab\0cd\0\xff";
+        let offset = &mut 0;
+        let mut strs = bytes.gread_iter::<&str>(offset, StrCtx::Delimiter(0));
+        assert_eq!(strs.next().unwrap().unwrap(), "ab");
+        assert_eq!(strs.offset(), 3);
+        assert_eq!(strs.next().unwrap().unwrap(), "cd");
+        // the last string is not UTF-8
+        assert!(strs.next().unwrap().is_err());
+        assert!(strs.next().is_none());
+        assert_eq!(*offset, 6);
+
+        let offset = &mut 0;
+        let words: Result<Vec<u16>, Error> = bytes.gread_iter(offset, BE).collect();
+        assert!(words.is_err());
+        let offset = &mut 1;
+        let words: Vec<u16> = bytes
+            .gread_iter(offset, BE)
+            .collect::<Result<_, Error>>()
+            .unwrap();
+        assert_eq!(words, [0x6200, 0x6364, 0x00ff]);
+        assert_eq!(*offset, 7);
+
+        // values taking up no bytes end the iterator instead of repeating forever
+        let offset = &mut 0;
+        let empty: Vec<&str> = bytes
+            .gread_iter(offset, StrCtx::Length(0))
+            .collect::<Result<_, Error>>()
+            .unwrap();
+        assert_eq!(empty, [""]);
+    }
+
     #[test]
     fn gread_with_byte() {
         use super::Pread;
@@ -0,0 +1,179 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+
+use memmap2::{Mmap, MmapMut};
+
+use crate::ctx::MeasureWith;
+
+/// A memory mapped file to read values from, and write them to if the mapping is writable.
+///
+/// `MmapSource` dereferences to the bytes of the file, so it gets [Pread](trait.Pread.html) (and
+/// [Pwrite](trait.Pwrite.html) for writable mappings) through `[u8]`: reads are bounds checked
+/// like for any slice, with `BadOffset` and `TooBig` errors, and borrowed values like `&str` live
+/// as long as the borrow of the mapping. Pages are only loaded as they are read.
+///
+/// Requires the `mmap` feature.
+///
+/// # Example
+/// ```rust
+/// use std::io::Write;
+/// use scroll::{ctx::StrCtx, MmapSource, Pread, Pwrite, LE};
+/// let path = std::env::temp_dir().join("scroll-mmap-source-example");
+/// std::fs::File::create(&path).unwrap().write_all(b"\x2a\x00\x00\x00hello").unwrap();
+///
+/// let mut source = unsafe { MmapSource::open_mut(&path) }.unwrap();
+/// source.pwrite_with(0xdeadbeefu32, 0, LE).unwrap();
+/// source.flush().unwrap();
+///
+/// let source = unsafe { MmapSource::open(&path) }.unwrap();
+/// assert_eq!(source.pread_with::<u32>(0, LE).unwrap(), 0xdeadbeef);
+/// let hello: &str = source.pread_with(4, StrCtx::Length(5)).unwrap();
+/// assert_eq!(hello, "hello");
+/// assert!(source.pread_with::<u32>(6, LE).is_err());
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct MmapSource<M = Mmap> {
+    map: M,
+}
+
+impl MmapSource {
+    /// Map the file at `path` read-only.
+    ///
+    /// # Safety
+    /// The file must not be modified, or truncated, while it is mapped, e.g. by other processes;
+    /// see [`Mmap::map`](https://docs.rs/memmap2/*/memmap2/struct.Mmap.html#method.map).
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::map(&File::open(path)?)
+    }
+
+    /// Map `file` read-only.
+    ///
+    /// # Safety
+    /// See [`open`](#method.open).
+    pub unsafe fn map(file: &File) -> io::Result<Self> {
+        Ok(MmapSource {
+            map: Mmap::map(file)?,
+        })
+    }
+}
+
+impl MmapSource<MmapMut> {
+    /// Map the file at `path` writable; writes go through to the file.
+    ///
+    /// # Safety
+    /// See [`open`](struct.MmapSource.html#method.open).
+    pub unsafe fn open_mut<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::map_mut(&OpenOptions::new().read(true).write(true).open(path)?)
+    }
+
+    /// Map `file`, which must be opened for reading and writing, writable.
+    ///
+    /// # Safety
+    /// See [`open`](struct.MmapSource.html#method.open).
+    pub unsafe fn map_mut(file: &File) -> io::Result<Self> {
+        Ok(MmapSource {
+            map: MmapMut::map_mut(file)?,
+        })
+    }
+
+    /// Write the modified pages back to the file, and wait for it to finish
+    pub fn flush(&self) -> io::Result<()> {
+        self.map.flush()
+    }
+}
+
+impl<M> MmapSource<M> {
+    /// Consume the source, returning the underlying mapping
+    pub fn into_inner(self) -> M {
+        self.map
+    }
+}
+
+impl From<Mmap> for MmapSource {
+    fn from(map: Mmap) -> Self {
+        MmapSource { map }
+    }
+}
+
+impl From<MmapMut> for MmapSource<MmapMut> {
+    fn from(map: MmapMut) -> Self {
+        MmapSource { map }
+    }
+}
+
+impl<M: Deref<Target = [u8]>> Deref for MmapSource<M> {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        &self.map
+    }
+}
+
+impl DerefMut for MmapSource<MmapMut> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.map
+    }
+}
+
+impl<M: Deref<Target = [u8]>> AsRef<[u8]> for MmapSource<M> {
+    fn as_ref(&self) -> &[u8] {
+        &self.map
+    }
+}
+
+impl<Ctx, M: Deref<Target = [u8]>> MeasureWith<Ctx> for MmapSource<M> {
+    #[inline]
+    fn measure_with(&self, _ctx: &Ctx) -> usize {
+        self.map.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Write;
+
+    use super::{MeasureWith, MmapSource};
+    use crate::ctx::{StrCtx, StrWith};
+    use crate::{Error, Pread, Pwrite, BE};
+
+    #[test]
+    fn mmap_source_reads_and_writes() {
+        let path = std::env::temp_dir().join(format!("scroll-mmap-test-{}", std::process::id()));
+        fs::File::create(&path)
+            .unwrap()
+            .write_all(&[0u8; 16])
+            .unwrap();
+
+        let mut source = unsafe { MmapSource::open_mut(&path) }.unwrap();
+        let offset = &mut 0;
+        source.gwrite_with(0x0102u16, offset, BE).unwrap();
+        source
+            .gwrite_with(StrWith("scroll"), offset, StrCtx::Length(8))
+            .unwrap();
+        assert!(matches!(
+            source.pwrite_with(0u32, 14, BE),
+            Err(Error::TooBig { .. })
+        ));
+        assert!(matches!(
+            source.pwrite_with(0u8, 16, BE),
+            Err(Error::BadOffset(16))
+        ));
+        source.flush().unwrap();
+        drop(source);
+
+        let source = unsafe { MmapSource::open(&path) }.unwrap();
+        assert_eq!(source.len(), 16);
+        assert_eq!(source.measure_with(&BE), 16);
+        assert_eq!(source.pread_with::<u16>(0, BE).unwrap(), 0x0102);
+        let name: &str = source.pread_with(2, StrCtx::Delimiter(0)).unwrap();
+        assert_eq!(name, "scroll");
+        let err = source.pread_with::<u64>(12, BE).unwrap_err();
+        assert_eq!(err.offset(), Some(12));
+        assert!(source.pread::<u8>(16).is_err());
+        drop(source);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,6 +1,8 @@
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
 use core::result;
 
-use crate::ctx::TryFromCtx;
+use crate::ctx::{MeasureWith, TryFromCtx};
 use crate::error;
 
 /// A very generic, contextual pread interface in Rust.
@@ -158,9 +160,101 @@ pub trait Pread<Ctx: Copy, E> {
         }
         Ok(())
     }
+
+    /// Returns an iterator reading one `N` after the other from `Self` starting at `offset`, using
+    /// the context `ctx`, and updating the offset past every value read.
+    ///
+    /// The iterator ends once the offset reaches the end of `Self`, and after the first error,
+    /// which leaves the offset at the value that failed; it also ends after a value which takes up
+    /// no bytes, since it would be read forever.
+    /// # Example
+    /// ```rust
+    /// use scroll::{Pread, Uleb128};
+    /// let bytes = [0x01, 0xe5, 0x8e, 0x26, 0x80];
+    /// let offset = &mut 0;
+    /// let mut values = bytes.gread_iter::<Uleb128>(offset, ());
+    /// assert_eq!(u64::from(values.next().unwrap().unwrap()), 1);
+    /// assert_eq!(u64::from(values.next().unwrap().unwrap()), 624485);
+    /// assert!(values.next().unwrap().is_err());
+    /// assert!(values.next().is_none());
+    /// assert_eq!(*offset, 4);
+    /// ```
+    #[inline]
+    fn gread_iter<'a, 'b, N: TryFromCtx<'a, Ctx, Self, Error = E>>(
+        &'a self,
+        offset: &'b mut usize,
+        ctx: Ctx,
+    ) -> GreadIter<'a, 'b, Self, N, Ctx, E>
+    where
+        Self: MeasureWith<Ctx>,
+    {
+        GreadIter {
+            src: self,
+            offset,
+            ctx,
+            done: false,
+            values: PhantomData,
+        }
+    }
+}
+
+/// The iterator returned by [`Pread::gread_iter`](trait.Pread.html#method.gread_iter)
+#[derive(Debug)]
+pub struct GreadIter<'a, 'b, S: ?Sized, N, Ctx, E> {
+    src: &'a S,
+    offset: &'b mut usize,
+    ctx: Ctx,
+    done: bool,
+    values: PhantomData<fn() -> result::Result<N, E>>,
+}
+
+impl<'a, 'b, S, N, Ctx, E> GreadIter<'a, 'b, S, N, Ctx, E>
+where
+    S: ?Sized,
+{
+    /// The offset of the next value to be read
+    #[inline]
+    pub fn offset(&self) -> usize {
+        *self.offset
+    }
+}
+
+impl<'a, 'b, S, N, Ctx, E> Iterator for GreadIter<'a, 'b, S, N, Ctx, E>
+where
+    S: Pread<Ctx, E> + MeasureWith<Ctx> + ?Sized,
+    N: TryFromCtx<'a, Ctx, S, Error = E>,
+    Ctx: Copy,
+{
+    type Item = result::Result<N, E>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || *self.offset >= self.src.measure_with(&self.ctx) {
+            return None;
+        }
+        let start = *self.offset;
+        let mut offset = start;
+        let read = self.src.gread_with(&mut offset, self.ctx);
+        match read {
+            Ok(_) => {
+                *self.offset = offset;
+                self.done = offset == start;
+            }
+            Err(_) => self.done = true,
+        }
+        Some(read)
+    }
+}
+
+impl<'a, 'b, S, N, Ctx, E> FusedIterator for GreadIter<'a, 'b, S, N, Ctx, E>
+where
+    S: Pread<Ctx, E> + MeasureWith<Ctx> + ?Sized,
+    N: TryFromCtx<'a, Ctx, S, Error = E>,
+    Ctx: Copy,
+{
 }
 
-impl<Ctx: Copy, E: From<error::Error>> Pread<Ctx, E> for [u8] {
+impl<Ctx: Copy, E: From<error::Error> + error::Locate> Pread<Ctx, E> for [u8] {
     fn gread_with<'a, N: TryFromCtx<'a, Ctx, Self, Error = E>>(
         &'a self,
         offset: &mut usize,
@@ -168,11 +262,13 @@ impl<Ctx: Copy, E: From<error::Error>> Pread<Ctx, E> for [u8] {
     ) -> result::Result<N, E> {
         let start = *offset;
         if start >= self.len() {
-            return Err(error::Error::BadOffset(start).into());
+            return Err(error::Error::BadOffset(0).at(start).into());
         }
-        N::try_from_ctx(&self[start..], ctx).map(|(n, size)| {
-            *offset += size;
-            n
-        })
+        N::try_from_ctx(&self[start..], ctx)
+            .map(|(n, size)| {
+                *offset += size;
+                n
+            })
+            .map_err(|err| err.at(start))
     }
 }
@@ -94,3 +94,269 @@ impl<Ctx: Copy, E: From<error::Error>> Pwrite<Ctx, E> for [u8] {
         n.try_into_ctx(dst, ctx)
     }
 }
+
+/// The least number of bytes a [`GrowableBuffer`] makes room for when writing past its end.
+#[cfg(feature = "std")]
+const MIN_RESERVE: usize = 16;
+
+/// A `Vec<u8>` backed byte buffer which is extended on demand instead of failing when written past
+/// its end, zero-filling any gap between its end and the offset written at.
+///
+/// Its `pwrite` family of methods mirrors [Pwrite](trait.Pwrite.html); anything which can be
+/// written into a `[u8]` and is `Clone` (which includes references) can be written. The buffer
+/// dereferences to `[u8]` for reading.
+///
+/// # Example
+/// ```
+/// use scroll::{GrowableBuffer, Pread, BE};
+/// let mut buffer = GrowableBuffer::new();
+/// let offset = &mut 0;
+/// buffer.gwrite_with(0xdeadbeefu32, offset, BE).unwrap();
+/// buffer.gwrite_with(&[1u8, 2, 3][..], offset, ()).unwrap();
+/// buffer.pwrite_with(0xffu8, 10, BE).unwrap();
+/// assert_eq!(buffer.pread_with::<u32>(0, BE).unwrap(), 0xdeadbeef);
+/// assert_eq!(buffer.into_inner(), [0xde, 0xad, 0xbe, 0xef, 1, 2, 3, 0, 0, 0, 0xff]);
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone)]
+pub struct GrowableBuffer {
+    bytes: Vec<u8>,
+}
+
+#[cfg(feature = "std")]
+impl GrowableBuffer {
+    /// Create an empty buffer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty buffer with room for `capacity` bytes before it has to reallocate
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::from(Vec::with_capacity(capacity))
+    }
+
+    /// Return the bytes written so far
+    pub fn into_inner(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// Write `n` at `offset`, with a default `Ctx`
+    #[inline]
+    pub fn pwrite<Ctx, N>(&mut self, n: N, offset: usize) -> error::Result<usize>
+    where
+        Ctx: Copy + Default,
+        N: TryIntoCtx<Ctx, Error = error::Error> + Clone,
+    {
+        self.pwrite_with(n, offset, Ctx::default())
+    }
+
+    /// Write `n` at `offset` with context `ctx`, extending the buffer as needed.
+    ///
+    /// How many bytes `n` takes up is only known once it has been written, so it is written into
+    /// a slice extending past the end of the buffer, which is doubled in size whenever the write
+    /// fails with `TooBig` or `BadOffset`. An error reporting the same thing for two sizes in a
+    /// row does not depend on the size, and is returned; the buffer is then left at its length
+    /// from before the write.
+    pub fn pwrite_with<Ctx, N>(&mut self, n: N, offset: usize, ctx: Ctx) -> error::Result<usize>
+    where
+        Ctx: Copy,
+        N: TryIntoCtx<Ctx, Error = error::Error> + Clone,
+    {
+        let len = self.bytes.len();
+        let mut reserve = core::cmp::max(len.saturating_sub(offset), MIN_RESERVE);
+        let mut last = None;
+        loop {
+            let end = offset
+                .checked_add(reserve)
+                .ok_or(error::Error::BadOffset(offset))?;
+            if self.bytes.len() < end {
+                self.bytes.resize(end, 0);
+            }
+            let err = match n.clone().try_into_ctx(&mut self.bytes[offset..], ctx) {
+                Ok(size) => {
+                    self.bytes.truncate(core::cmp::max(len, offset + size));
+                    return Ok(size);
+                }
+                Err(err) => err,
+            };
+            // errors of nested writes, such as the elements of a `Vec`, come located in `At`
+            let kind = err.kind();
+            let this = match kind {
+                Some(error::Cause::BadOffset(bad)) => Some((bad, usize::MAX)),
+                Some(error::Cause::TooBig { size, len }) => Some((size, len)),
+                _ => None,
+            };
+            let retry = match kind {
+                // a nested write at `bad` may need up to `bad` more bytes than the last attempt had
+                Some(error::Cause::BadOffset(bad)) => last != this || reserve / 2 <= bad,
+                Some(error::Cause::TooBig { .. }) => last != this,
+                _ => false,
+            };
+            if !retry {
+                self.bytes.truncate(len);
+                return Err(err);
+            }
+            last = this;
+            reserve = reserve.saturating_mul(2);
+        }
+    }
+
+    /// Write `n` at `offset`, with a default `Ctx`. Updates the offset.
+    #[inline]
+    pub fn gwrite<Ctx, N>(&mut self, n: N, offset: &mut usize) -> error::Result<usize>
+    where
+        Ctx: Copy + Default,
+        N: TryIntoCtx<Ctx, Error = error::Error> + Clone,
+    {
+        self.gwrite_with(n, offset, Ctx::default())
+    }
+
+    /// Write `n` at `offset` with context `ctx`. Updates the offset.
+    #[inline]
+    pub fn gwrite_with<Ctx, N>(
+        &mut self,
+        n: N,
+        offset: &mut usize,
+        ctx: Ctx,
+    ) -> error::Result<usize>
+    where
+        Ctx: Copy,
+        N: TryIntoCtx<Ctx, Error = error::Error> + Clone,
+    {
+        let size = self.pwrite_with(n, *offset, ctx)?;
+        *offset += size;
+        Ok(size)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Vec<u8>> for GrowableBuffer {
+    fn from(bytes: Vec<u8>) -> Self {
+        GrowableBuffer { bytes }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<GrowableBuffer> for Vec<u8> {
+    fn from(buffer: GrowableBuffer) -> Self {
+        buffer.bytes
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::ops::Deref for GrowableBuffer {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+#[cfg(feature = "std")]
+impl AsRef<[u8]> for GrowableBuffer {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+#[cfg(feature = "std")]
+impl<Ctx> crate::ctx::MeasureWith<Ctx> for GrowableBuffer {
+    #[inline]
+    fn measure_with(&self, _ctx: &Ctx) -> usize {
+        self.bytes.len()
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "std")]
+mod tests {
+    use super::*;
+    use crate::ctx::{StrCtx, StrWith};
+    use crate::{IOwrite, Pread, Pwrite, BE, LE};
+
+    #[derive(Clone, Copy)]
+    struct Record<'a> {
+        id: u16,
+        payload: &'a [u8],
+        checksum: u64,
+    }
+
+    impl<'a> TryIntoCtx<crate::Endian> for Record<'a> {
+        type Error = error::Error;
+        fn try_into_ctx(self, dst: &mut [u8], ctx: crate::Endian) -> error::Result<usize> {
+            let offset = &mut 0;
+            dst.gwrite_with(self.id, offset, ctx)?;
+            dst.gwrite(self.payload, offset)?;
+            dst.gwrite_with(self.checksum, offset, ctx)?;
+            Ok(*offset)
+        }
+    }
+
+    #[test]
+    fn growable_buffer_grows_for_nested_writes() {
+        let payload = [0xaa; 100];
+        let record = Record {
+            id: 0x1234,
+            payload: &payload,
+            checksum: 0x0102030405060708,
+        };
+        let mut buffer = GrowableBuffer::new();
+        let offset = &mut 0;
+        for _ in 0..3 {
+            assert_eq!(buffer.gwrite_with(record, offset, LE).unwrap(), 110);
+        }
+        assert_eq!(*offset, 330);
+        assert_eq!(buffer.len(), 330);
+        assert_eq!(buffer.pread_with::<u16>(220, LE).unwrap(), 0x1234);
+        assert_eq!(
+            buffer.pread_with::<u64>(322, LE).unwrap(),
+            0x0102030405060708
+        );
+    }
+
+    #[test]
+    fn growable_buffer_fills_gaps_and_overwrites() {
+        let mut buffer = GrowableBuffer::from(vec![0xff; 4]);
+        buffer.pwrite_with(0xbeefu16, 8, BE).unwrap();
+        assert_eq!(*buffer, [0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0, 0xbe, 0xef]);
+        buffer.pwrite_with(0x1122u16, 1, BE).unwrap();
+        assert_eq!(*buffer, [0xff, 0x11, 0x22, 0xff, 0, 0, 0, 0, 0xbe, 0xef]);
+        buffer.pwrite_with(0x33u8, 9, BE).unwrap();
+        assert_eq!(buffer.len(), 10);
+        assert_eq!(Vec::from(buffer)[9], 0x33);
+    }
+
+    #[test]
+    fn growable_buffer_returns_errors_not_caused_by_its_size() {
+        let mut buffer = GrowableBuffer::from(vec![1, 2, 3]);
+        assert!(buffer
+            .pwrite_with(StrWith("hello"), 2, StrCtx::Length(3))
+            .is_err());
+        assert!(buffer
+            .pwrite_with(StrWith("hi there"), 8, StrCtx::Delimiter(b' '))
+            .is_err());
+        assert_eq!(*buffer, [1, 2, 3]);
+        assert_eq!(
+            buffer
+                .pwrite_with(StrWith("hello"), 2, StrCtx::Length(6))
+                .unwrap(),
+            6
+        );
+        assert_eq!(*buffer, [1, 2, b'h', b'e', b'l', b'l', b'o', 0]);
+    }
+
+    #[test]
+    fn growable_buffer_grows_for_sequences() {
+        let values: Vec<u32> = (0..100).collect();
+        let mut buffer = GrowableBuffer::new();
+        assert_eq!(buffer.pwrite_with(values.clone(), 0, LE).unwrap(), 400);
+        assert_eq!(buffer.len(), 400);
+        assert_eq!(crate::ctx::MeasureWith::measure_with(&buffer, &LE), 400);
+        assert_eq!(buffer.pread_with::<u32>(396, LE).unwrap(), 99);
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        assert_eq!(cursor.iowrite_try_with(values, BE).unwrap(), 400);
+        let bytes = cursor.into_inner();
+        assert_eq!(bytes.len(), 400);
+        assert_eq!(bytes.pread_with::<u32>(396, BE).unwrap(), 99);
+    }
+}
@@ -0,0 +1,307 @@
+use std::io::{self, ErrorKind, Read, Seek, SeekFrom};
+
+use crate::ctx::{FromCtx, SizeWith, TryFromCtx};
+use crate::error;
+use crate::lesser::Scratch;
+
+/// A least recently used cache of fixed size pages of a stream
+#[derive(Debug)]
+struct PageCache {
+    page_size: usize,
+    capacity: usize,
+    /// The index of each page, when it was last used, and its bytes; pages at the end of the stream
+    /// may be short
+    pages: Vec<(u64, u64, Vec<u8>)>,
+    clock: u64,
+}
+
+/// Positional reads from a `Read + Seek` stream like a file, so values can be read at absolute
+/// offsets without loading the whole stream into memory first; the counterpart of
+/// [Pread](trait.Pread.html) for streams.
+///
+/// Since values can not borrow from the stream, they are always owned: `pread_with` reads
+/// [`FromCtx`](ctx/trait.FromCtx.html) + [`SizeWith`](ctx/trait.SizeWith.html) types like
+/// [`IOread`](trait.IOread.html), and `pread_try_with` reads
+/// [`TryFromCtx`](ctx/trait.TryFromCtx.html) types through a buffer growing as they ask for more
+/// bytes, like [`ioread_try_with`](trait.IOread.html#method.ioread_try_with).
+///
+/// Every read seeks the stream to its offset first; with a page cache, created with
+/// [`with_page_cache`](#method.with_page_cache), repeated small reads close to each other are
+/// served from memory instead.
+///
+/// # Example
+/// ```rust
+/// use std::io::Cursor;
+/// use scroll::{SeekReader, Uleb128, BE};
+/// let stream = Cursor::new([0xde, 0xad, 0xbe, 0xef, 0xe5, 0x8e, 0x26]);
+/// let mut reader = SeekReader::with_page_cache(stream, 4, 2);
+/// assert_eq!(reader.pread_with::<u16, _>(2, BE).unwrap(), 0xbeef);
+/// let offset = &mut 4;
+/// let uleb: Uleb128 = reader.gread_try_with(offset, ()).unwrap();
+/// assert_eq!(u64::from(uleb), 624485);
+/// assert_eq!(*offset, 7);
+/// assert!(reader.pread_with::<u32, _>(4, BE).is_err());
+/// ```
+#[derive(Debug)]
+pub struct SeekReader<R> {
+    reader: R,
+    cache: Option<PageCache>,
+}
+
+impl<R: Read + Seek> SeekReader<R> {
+    /// Read from `reader` directly
+    pub fn new(reader: R) -> Self {
+        SeekReader {
+            reader,
+            cache: None,
+        }
+    }
+
+    /// Read from `reader` through a cache of the `pages` most recently read pages of `page_size`
+    /// bytes
+    ///
+    /// # Panics
+    /// If `page_size` or `pages` is zero
+    pub fn with_page_cache(reader: R, page_size: usize, pages: usize) -> Self {
+        assert!(
+            page_size > 0 && pages > 0,
+            "the page cache can not be empty"
+        );
+        SeekReader {
+            reader,
+            cache: Some(PageCache {
+                page_size,
+                capacity: pages,
+                pages: Vec::with_capacity(pages),
+                clock: 0,
+            }),
+        }
+    }
+
+    /// The stream read from
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Consume the reader, returning the stream read from, which is at an unspecified position
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Fill `buf` with the bytes of the stream at `offset`, failing with `UnexpectedEof` if the
+    /// stream ends before
+    pub fn read_exact_at(&mut self, offset: u64, buf: &mut [u8]) -> io::Result<()> {
+        let cache = match self.cache {
+            Some(ref mut cache) => cache,
+            None => {
+                self.reader.seek(SeekFrom::Start(offset))?;
+                return self.reader.read_exact(buf);
+            }
+        };
+        let page_size = cache.page_size as u64;
+        let mut filled = 0;
+        while filled < buf.len() {
+            let at = offset + filled as u64;
+            let page = cache.page(&mut self.reader, at / page_size)?;
+            let skip = (at % page_size) as usize;
+            let bytes = page.get(skip..).unwrap_or_default();
+            if bytes.is_empty() {
+                return Err(ErrorKind::UnexpectedEof.into());
+            }
+            let take = bytes.len().min(buf.len() - filled);
+            buf[filled..filled + take].copy_from_slice(&bytes[..take]);
+            filled += take;
+        }
+        Ok(())
+    }
+
+    /// Reads a `N` at `offset` with `ctx`
+    pub fn pread_with<N, Ctx>(&mut self, offset: u64, ctx: Ctx) -> io::Result<N>
+    where
+        N: FromCtx<Ctx> + SizeWith<Ctx>,
+        Ctx: Copy,
+    {
+        let mut scratch = Scratch::new(N::size_with(&ctx))?;
+        let buf = scratch.as_mut();
+        self.read_exact_at(offset, buf)?;
+        Ok(N::from_ctx(buf, ctx))
+    }
+
+    /// Reads a `N` at `offset` with `ctx`, and advances the offset past it
+    pub fn gread_with<N, Ctx>(&mut self, offset: &mut u64, ctx: Ctx) -> io::Result<N>
+    where
+        N: FromCtx<Ctx> + SizeWith<Ctx>,
+        Ctx: Copy,
+    {
+        let n = self.pread_with(*offset, ctx)?;
+        *offset += N::size_with(&ctx) as u64;
+        Ok(n)
+    }
+
+    /// Reads a `N`, whose parsing can fail or whose size depends on its contents, at `offset`
+    /// with `ctx`; parsing errors are located at their offset in the stream, and IO errors are
+    /// returned as [`Error::IO`](enum.Error.html#variant.IO).
+    pub fn pread_try_with<N, Ctx>(&mut self, offset: u64, ctx: Ctx) -> error::Result<N>
+    where
+        N: for<'a> TryFromCtx<'a, Ctx, Error = error::Error>,
+        Ctx: Copy,
+    {
+        let mut ignored = offset;
+        self.gread_try_with(&mut ignored, ctx)
+    }
+
+    /// Reads a `N` like [`pread_try_with`](#method.pread_try_with), and advances the offset past it
+    pub fn gread_try_with<N, Ctx>(&mut self, offset: &mut u64, ctx: Ctx) -> error::Result<N>
+    where
+        N: for<'a> TryFromCtx<'a, Ctx, Error = error::Error>,
+        Ctx: Copy,
+    {
+        /// The most bytes read at once, so that bogus sizes fail at the end of the stream
+        /// instead of allocating them up front
+        const STEP: usize = 4096;
+        let mut buf = Vec::new();
+        loop {
+            let needed = match N::try_from_ctx(&buf, ctx) {
+                Ok((n, size)) if size <= buf.len() => {
+                    *offset += size as u64;
+                    return Ok(n);
+                }
+                Ok((_, size)) => size,
+                Err(err) => match error::Locate::needed_len(&err, buf.len()) {
+                    Some(needed) => needed,
+                    None => {
+                        return Err(match usize::try_from(*offset) {
+                            Ok(offset) => err.at(offset),
+                            Err(_) => err,
+                        })
+                    }
+                },
+            };
+            while buf.len() < needed {
+                let start = buf.len();
+                buf.resize(start + (needed - start).min(STEP), 0);
+                self.read_exact_at(*offset + start as u64, &mut buf[start..])?;
+            }
+        }
+    }
+}
+
+impl PageCache {
+    /// The page at `index`, read from `reader` unless it is cached
+    fn page<R: Read + Seek>(&mut self, reader: &mut R, index: u64) -> io::Result<&[u8]> {
+        self.clock += 1;
+        let clock = self.clock;
+        if let Some(slot) = self.pages.iter().position(|(i, _, _)| *i == index) {
+            self.pages[slot].1 = clock;
+            return Ok(&self.pages[slot].2);
+        }
+        let mut page = if self.pages.len() < self.capacity {
+            Vec::with_capacity(self.page_size)
+        } else {
+            // evict the least recently used page, reusing its buffer
+            let slot = (0..self.pages.len())
+                .min_by_key(|slot| self.pages[*slot].1)
+                .unwrap_or(0);
+            let (_, _, mut page) = self.pages.swap_remove(slot);
+            page.clear();
+            page
+        };
+        reader.seek(SeekFrom::Start(index * self.page_size as u64))?;
+        reader
+            .by_ref()
+            .take(self.page_size as u64)
+            .read_to_end(&mut page)?;
+        self.pages.push((index, clock, page));
+        Ok(&self.pages[self.pages.len() - 1].2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+    use std::io::{self, Cursor, Read, Seek, SeekFrom};
+
+    use super::SeekReader;
+    use crate::{Error, Uleb128, BE, LE};
+
+    /// Counts the reads reaching the stream
+    struct Counting {
+        inner: Cursor<Vec<u8>>,
+        reads: usize,
+    }
+
+    impl Read for Counting {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.reads += 1;
+            self.inner.read(buf)
+        }
+    }
+
+    impl Seek for Counting {
+        fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    fn stream() -> Vec<u8> {
+        let mut bytes: Vec<u8> = (0..64).collect();
+        bytes[40..46].copy_from_slice(b"hello\0");
+        bytes[60..].copy_from_slice(&[0x80; 4]);
+        bytes
+    }
+
+    #[test]
+    fn seek_reader_reads_at_offsets() {
+        for mut reader in [
+            SeekReader::new(Cursor::new(stream())),
+            SeekReader::with_page_cache(Cursor::new(stream()), 16, 2),
+        ] {
+            assert_eq!(reader.pread_with::<u32, _>(14, BE).unwrap(), 0x0e0f1011);
+            assert_eq!(reader.pread_with::<u8, _>(0, LE).unwrap(), 0);
+            let offset = &mut 40;
+            let hello: CString = reader.gread_try_with(offset, ()).unwrap();
+            assert_eq!(hello.as_bytes(), b"hello");
+            assert_eq!(*offset, 46);
+            assert_eq!(reader.gread_with::<u16, _>(offset, LE).unwrap(), 0x2f2e);
+            assert_eq!(*offset, 48);
+
+            let err = reader.pread_with::<u64, _>(60, LE).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+            assert!(matches!(
+                reader.pread_try_with::<Uleb128, _>(61, ()),
+                Err(Error::IO(_))
+            ));
+            let err = reader.pread_try_with::<CString, _>(64, ()).unwrap_err();
+            assert!(matches!(err, Error::IO(_)));
+        }
+    }
+
+    #[test]
+    fn seek_reader_caches_pages() {
+        let stream = Counting {
+            inner: Cursor::new(stream()),
+            reads: 0,
+        };
+        let mut reader = SeekReader::with_page_cache(stream, 16, 2);
+        for offset in 0..12 {
+            reader.pread_with::<u32, _>(offset, LE).unwrap();
+        }
+        let reads = reader.get_ref().reads;
+        // a page straddling read pulls in the next page too
+        reader.pread_with::<u32, _>(14, LE).unwrap();
+        reader.pread_with::<u32, _>(20, LE).unwrap();
+        reader.pread_with::<u32, _>(2, LE).unwrap();
+        assert!(reader.get_ref().reads > reads);
+        let reads = reader.get_ref().reads;
+        reader.pread_with::<u32, _>(18, LE).unwrap();
+        reader.pread_with::<u32, _>(4, LE).unwrap();
+        assert_eq!(reader.get_ref().reads, reads);
+        // a third page evicts the least recently used one
+        reader.pread_with::<u8, _>(40, LE).unwrap();
+        let reads = reader.get_ref().reads;
+        reader.pread_with::<u8, _>(0, LE).unwrap();
+        assert_eq!(reader.get_ref().reads, reads);
+        reader.pread_with::<u8, _>(16, LE).unwrap();
+        assert!(reader.get_ref().reads > reads);
+    }
+}
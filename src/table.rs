@@ -0,0 +1,318 @@
+use core::fmt;
+use core::iter::FusedIterator;
+use core::marker::PhantomData;
+use core::result;
+
+use crate::ctx::{SizeWith, TryFromCtx};
+use crate::{error, Pread};
+
+/// A lazily read table of fixed size records, like the symbols or relocations of a binary,
+/// borrowing the bytes it is read from.
+///
+/// Every record takes up `T::size_with(&ctx)` bytes, so records are read on demand with
+/// [`get`](#method.get) or iterated over in either direction without allocating or parsing the ones
+/// in between; reads are fallible, so both yield `Result`s.
+///
+/// With the `rayon` feature, [`par_iter`](#method.par_iter) reads the records in parallel.
+///
+/// # Example
+/// ```rust
+/// use scroll::{Table, BE};
+/// let bytes = [0x00, 0x01, 0x00, 0x02, 0x00, 0x03, 0xff];
+/// let table = Table::<u16, _>::new(&bytes, BE);
+/// assert_eq!(table.len(), 3);
+/// assert_eq!(table.get(1).unwrap().unwrap(), 2);
+/// assert!(table.get(3).is_none());
+/// let reversed: Vec<u16> = table.iter().rev().map(Result::unwrap).collect();
+/// assert_eq!(reversed, [3, 2, 1]);
+/// assert!(Table::<u16, _>::with_len(&bytes, 4, BE).is_err());
+/// ```
+pub struct Table<'a, T, Ctx> {
+    bytes: &'a [u8],
+    ctx: Ctx,
+    size: usize,
+    len: usize,
+    records: PhantomData<fn() -> T>,
+}
+
+impl<'a, T, Ctx> Table<'a, T, Ctx>
+where
+    T: TryFromCtx<'a, Ctx> + SizeWith<Ctx>,
+    T::Error: From<error::Error> + error::Locate,
+    Ctx: Copy,
+{
+    /// A table of as many records as fit in `bytes`; any bytes left over at the end are ignored
+    pub fn new(bytes: &'a [u8], ctx: Ctx) -> Self {
+        let size = T::size_with(&ctx);
+        let len = bytes.len().checked_div(size).unwrap_or(0);
+        Table {
+            bytes: &bytes[..len * size],
+            ctx,
+            size,
+            len,
+            records: PhantomData,
+        }
+    }
+
+    /// A table of `len` records at the start of `bytes`, failing with `TooBig` if they don't fit
+    pub fn with_len(bytes: &'a [u8], len: usize, ctx: Ctx) -> error::Result<Self> {
+        let size = T::size_with(&ctx);
+        match len.checked_mul(size) {
+            Some(total) if total <= bytes.len() => Ok(Table {
+                bytes: &bytes[..total],
+                ctx,
+                size,
+                len,
+                records: PhantomData,
+            }),
+            total => Err(error::Error::TooBig {
+                size: total.unwrap_or(usize::MAX),
+                len: bytes.len(),
+            }),
+        }
+    }
+
+    /// Reads the record at `index`, or `None` if it is out of bounds
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<result::Result<T, T::Error>> {
+        if index < self.len {
+            Some(self.bytes.pread_with(index * self.size, self.ctx))
+        } else {
+            None
+        }
+    }
+
+    /// An iterator reading the records in order
+    #[inline]
+    pub fn iter(&self) -> TableIter<'a, T, Ctx> {
+        TableIter {
+            table: *self,
+            front: 0,
+            back: self.len,
+        }
+    }
+
+    /// Reads the records in parallel; the records are produced in order when collected.
+    ///
+    /// Requires the `rayon` feature.
+    #[cfg(feature = "rayon")]
+    pub fn par_iter(
+        &self,
+    ) -> impl rayon::iter::IndexedParallelIterator<Item = result::Result<T, T::Error>> + 'a
+    where
+        T: Send,
+        T::Error: Send,
+        Ctx: Send + Sync + 'a,
+    {
+        use rayon::iter::{IntoParallelIterator, ParallelIterator};
+        let table = *self;
+        (0..self.len)
+            .into_par_iter()
+            .map(move |index| table.bytes.pread_with(index * table.size, table.ctx))
+    }
+}
+
+impl<'a, T, Ctx> Table<'a, T, Ctx> {
+    /// The number of records in the table
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the table has no records
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The bytes of the records in the table, without any left over bytes following them
+    #[inline]
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+}
+
+// not derived, so that neither needs `T` to implement them
+impl<'a, T, Ctx: Copy> Clone for Table<'a, T, Ctx> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T, Ctx: Copy> Copy for Table<'a, T, Ctx> {}
+
+impl<'a, T, Ctx: fmt::Debug> fmt::Debug for Table<'a, T, Ctx> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Table")
+            .field("len", &self.len)
+            .field("size", &self.size)
+            .field("ctx", &self.ctx)
+            .finish()
+    }
+}
+
+impl<'a, T, Ctx> IntoIterator for Table<'a, T, Ctx>
+where
+    T: TryFromCtx<'a, Ctx> + SizeWith<Ctx>,
+    T::Error: From<error::Error> + error::Locate,
+    Ctx: Copy,
+{
+    type Item = result::Result<T, T::Error>;
+    type IntoIter = TableIter<'a, T, Ctx>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, Ctx> IntoIterator for &Table<'a, T, Ctx>
+where
+    T: TryFromCtx<'a, Ctx> + SizeWith<Ctx>,
+    T::Error: From<error::Error> + error::Locate,
+    Ctx: Copy,
+{
+    type Item = result::Result<T, T::Error>;
+    type IntoIter = TableIter<'a, T, Ctx>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// The iterator returned by [`Table::iter`](struct.Table.html#method.iter)
+pub struct TableIter<'a, T, Ctx> {
+    table: Table<'a, T, Ctx>,
+    /// The records in `front..back` are yet to be read
+    front: usize,
+    back: usize,
+}
+
+impl<'a, T, Ctx: Copy> Clone for TableIter<'a, T, Ctx> {
+    fn clone(&self) -> Self {
+        TableIter {
+            table: self.table,
+            front: self.front,
+            back: self.back,
+        }
+    }
+}
+
+impl<'a, T, Ctx: fmt::Debug> fmt::Debug for TableIter<'a, T, Ctx> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("TableIter")
+            .field("table", &self.table)
+            .field("front", &self.front)
+            .field("back", &self.back)
+            .finish()
+    }
+}
+
+impl<'a, T, Ctx> Iterator for TableIter<'a, T, Ctx>
+where
+    T: TryFromCtx<'a, Ctx> + SizeWith<Ctx>,
+    T::Error: From<error::Error> + error::Locate,
+    Ctx: Copy,
+{
+    type Item = result::Result<T, T::Error>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
+        self.table.get(self.front - 1)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+
+    #[inline]
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.front = self.front.saturating_add(n).min(self.back);
+        self.next()
+    }
+}
+
+impl<'a, T, Ctx> DoubleEndedIterator for TableIter<'a, T, Ctx>
+where
+    T: TryFromCtx<'a, Ctx> + SizeWith<Ctx>,
+    T::Error: From<error::Error> + error::Locate,
+    Ctx: Copy,
+{
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        self.table.get(self.back)
+    }
+}
+
+impl<'a, T, Ctx> ExactSizeIterator for TableIter<'a, T, Ctx>
+where
+    T: TryFromCtx<'a, Ctx> + SizeWith<Ctx>,
+    T::Error: From<error::Error> + error::Locate,
+    Ctx: Copy,
+{
+}
+
+impl<'a, T, Ctx> FusedIterator for TableIter<'a, T, Ctx>
+where
+    T: TryFromCtx<'a, Ctx> + SizeWith<Ctx>,
+    T::Error: From<error::Error> + error::Locate,
+    Ctx: Copy,
+{
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Table;
+    use crate::{Error, BE, LE};
+
+    #[test]
+    fn table_reads_lazily() {
+        let bytes = [1, 0, 0, 0, 2, 0, 0, 0, 3, 0, 0, 0, 4, 0];
+        let table = Table::<u32, _>::new(&bytes, LE);
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.as_bytes(), &bytes[..12]);
+        assert_eq!(table.get(2).unwrap().unwrap(), 3);
+        assert!(table.get(3).is_none());
+
+        let mut iter = table.iter();
+        assert_eq!(iter.len(), 3);
+        assert_eq!(iter.next().unwrap().unwrap(), 1);
+        assert_eq!(iter.next_back().unwrap().unwrap(), 3);
+        assert_eq!(iter.len(), 1);
+        assert_eq!(iter.next().unwrap().unwrap(), 2);
+        assert!(iter.next().is_none());
+        assert!(iter.next_back().is_none());
+        assert_eq!(table.iter().nth(1).unwrap().unwrap(), 2);
+        assert!(table.iter().nth(5).is_none());
+        let sum: u32 = table.into_iter().map(Result::unwrap).sum();
+        assert_eq!(sum, 6);
+
+        let table = Table::<u16, _>::with_len(&bytes, 7, BE).unwrap();
+        assert_eq!(table.get(6).unwrap().unwrap(), 0x0400);
+        assert!(matches!(
+            Table::<u16, _>::with_len(&bytes, 8, BE),
+            Err(Error::TooBig { size: 16, len: 14 })
+        ));
+        assert!(Table::<u64, _>::new(&bytes[..7], LE).is_empty());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn table_par_iter() {
+        use rayon::iter::ParallelIterator;
+
+        let bytes: Vec<u8> = (0..=255).collect();
+        let table = Table::<u16, _>::new(&bytes, BE);
+        let words: Vec<u16> = table.par_iter().map(Result::unwrap).collect();
+        let expected: Vec<u16> = table.iter().map(Result::unwrap).collect();
+        assert_eq!(words, expected);
+        assert_eq!(words.len(), 128);
+    }
+}
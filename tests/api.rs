@@ -170,6 +170,19 @@ fn lifetime_passthrough() {
     let _res = lifetime_passthrough_(&segments, "__text");
 }
 
+#[cfg(feature = "std")]
+#[test]
+fn pread_vec_with_count() {
+    let size = ::std::mem::size_of::<Section>();
+    let bytes = vec![0u8; 2 * size];
+    let sections: Vec<Section> = bytes.pread_with(0, (2, ())).unwrap();
+    assert_eq!(sections.len(), 2);
+    let none: Vec<Section> = bytes.pread_with(size, (0, ())).unwrap();
+    assert!(none.is_empty());
+    let res: Result<Vec<Section>> = bytes.pread_with(size, (2, ()));
+    assert_eq!(res.err().and_then(|err| err.offset()), Some(2 * size));
+}
+
 #[cfg(feature = "std")]
 #[derive(Default)]
 #[repr(packed)]
@@ -218,6 +231,116 @@ fn ioread_api() {
     assert_eq!({ foo_.bar }, bar);
 }
 
+/// A type too big for the stack scratch buffer of `IOread` and `IOwrite`
+#[cfg(feature = "std")]
+#[derive(Debug, PartialEq)]
+struct Big([u8; 300]);
+
+#[cfg(feature = "std")]
+impl scroll::ctx::FromCtx<scroll::Endian> for Big {
+    fn from_ctx(bytes: &[u8], _: scroll::Endian) -> Self {
+        let mut big = Big([0; 300]);
+        big.0.copy_from_slice(bytes);
+        big
+    }
+}
+
+#[cfg(feature = "std")]
+impl scroll::ctx::IntoCtx<scroll::Endian> for Big {
+    fn into_ctx(self, bytes: &mut [u8], _: scroll::Endian) {
+        bytes.copy_from_slice(&self.0);
+    }
+}
+
+#[cfg(feature = "std")]
+impl scroll::ctx::SizeWith<scroll::Endian> for Big {
+    fn size_with(_: &scroll::Endian) -> usize {
+        300
+    }
+}
+
+/// A type claiming a size no buffer can be allocated for
+#[cfg(feature = "std")]
+struct Huge;
+
+#[cfg(feature = "std")]
+impl scroll::ctx::FromCtx<scroll::Endian> for Huge {
+    fn from_ctx(_: &[u8], _: scroll::Endian) -> Self {
+        Huge
+    }
+}
+
+#[cfg(feature = "std")]
+impl scroll::ctx::SizeWith<scroll::Endian> for Huge {
+    fn size_with(_: &scroll::Endian) -> usize {
+        usize::MAX
+    }
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn ioread_iowrite_big_types() {
+    use std::io::{Cursor, ErrorKind};
+
+    use scroll::{IOread, IOwrite, LE};
+
+    let mut big = Big([0; 300]);
+    for (i, byte) in big.0.iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+    let mut cursor = Cursor::new(Vec::new());
+    cursor.iowrite_with(Big(big.0), LE).unwrap();
+    assert_eq!(cursor.get_ref().len(), 300);
+    cursor.set_position(0);
+    assert_eq!(cursor.ioread_with::<Big>(LE).unwrap(), big);
+    assert_eq!(
+        cursor.ioread_with::<Big>(LE).unwrap_err().kind(),
+        ErrorKind::UnexpectedEof
+    );
+
+    let mut cursor = Cursor::new([0u8; 8]);
+    let error = cursor.ioread_with::<Huge>(LE).err().unwrap();
+    assert_eq!(error.kind(), ErrorKind::OutOfMemory);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn ioread_iowrite_try() {
+    use std::ffi::CString;
+    use std::io::{Cursor, ErrorKind};
+
+    use scroll::ctx::{StrCtx, StrWith};
+    use scroll::{Error, IOread, IOwrite, Sleb128, Uleb32, BE};
+
+    let mut cursor = Cursor::new(Vec::new());
+    cursor.iowrite_try_with(Sleb128::from(-123456), ()).unwrap();
+    cursor.iowrite_try_with(0xbeefu16, BE).unwrap();
+    let hello = CString::new("hello").unwrap();
+    assert_eq!(cursor.iowrite_try_with(hello.clone(), ()).unwrap(), 6);
+    // nothing is written if writing fails
+    assert!(cursor
+        .iowrite_try_with(StrWith("hello"), StrCtx::Length(2))
+        .is_err());
+    cursor.iowrite_try_with(&[0xffu8; 5][..], ()).unwrap();
+
+    cursor.set_position(0);
+    assert_eq!(
+        i64::from(cursor.ioread_try_with::<Sleb128>(()).unwrap()),
+        -123456
+    );
+    assert_eq!(cursor.ioread_try_with::<u16>(BE).unwrap(), 0xbeef);
+    assert_eq!(cursor.ioread_try_with::<CString>(()).unwrap(), hello);
+    match cursor.ioread_try_with::<Uleb32>(()) {
+        Err(Error::BadInput { .. }) => {}
+        other => panic!("expected BadInput, got {:?}", other),
+    }
+    assert_eq!(cursor.position(), 16);
+    match cursor.ioread_try_with::<u32>(BE) {
+        Err(Error::IO(err)) => assert_eq!(err.kind(), ErrorKind::UnexpectedEof),
+        other => panic!("expected an IO error, got {:?}", other),
+    }
+}
+
 #[repr(packed)]
 struct Bar {
     foo: i32,